@@ -2,49 +2,229 @@
 #![allow(unused_imports)]
 #![allow(dead_code)]
 
-use crate::sintax::{Statement, Expresion, Literal};
-use crate::table::SymbolTable;
+use crate::sintax::{Statement, Expresion, Literal, DataType, NodeId, IntType, Pattern};
+use crate::table::{SymbolTable, SymbolId, SymbolKind, ScopeKind, UseType};
+use std::collections::{HashMap, HashSet};
 use crate::visitor::{Visitable, Visitor};
+use crate::semantic::Promotion;
 use crate::lexer::{Token, Number};
+use unicode_normalization::UnicodeNormalization;
+
+// What fixed width arithmetic does when the result doesn't fit in its type
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Overflow {
+  Wrap,
+  Panic,
+}
 
 #[derive(Debug)]
 pub struct PythonGenerator {
   programng: Vec<Statement>,
   indent: usize,
   simbol_table: SymbolTable,
+  // Type of every expression, computed by the semantic analysis
+  types: HashMap<NodeId, DataType>,
+  // Operands the semantic analysis converted to float
+  promotions: HashMap<NodeId, Promotion>,
+  overflow: Overflow,
   generated_code: String,
 }
 
 impl PythonGenerator {
-  pub fn new(program: Vec<Statement>, simbol_table: SymbolTable) -> Self {
+  pub fn new(program: Vec<Statement>, simbol_table: SymbolTable, types: HashMap<NodeId, DataType>, promotions: HashMap<NodeId, Promotion>, overflow: Overflow) -> Self {
     Self {
       programng: program,
       indent: 0,
       simbol_table,
+      types,
+      promotions,
+      overflow,
       generated_code: String::new(),
     }
   }
 
   pub fn generate(&mut self) -> String {
     let mut code = String::new();
-    let mut visitor = PythonVisitor::new(self.indent);
+    let names = python_names(&self.simbol_table);
+    let mut visitor = PythonVisitor::new(self.indent, &self.simbol_table, &self.types, &self.promotions, self.overflow, names);
     for statement in &mut self.programng {
       let generate = statement.accept(&mut visitor);
       code.push_str(&generate);
       code.push('\n');
     }
-    code
+    // the helpers used by the program go before it
+    let mut runtime = String::new();
+    for (name, definition) in RUNTIME {
+      if visitor.helpers.contains(name) {
+        runtime.push_str(definition);
+        runtime.push('\n');
+      }
+    }
+    runtime + &code
   }
 }
 
 #[derive(Debug, Clone)]
-struct PythonVisitor {
+struct PythonVisitor<'a> {
   indent: usize,
+  table: &'a SymbolTable,
+  types: &'a HashMap<NodeId, DataType>,
+  promotions: &'a HashMap<NodeId, Promotion>,
+  overflow: Overflow,
+  // Python name of every declaration, see `python_names`
+  names: HashMap<SymbolId, String>,
+  // Runtime helpers the generated code calls
+  helpers: HashSet<&'static str>,
+  // Temporaries holding the indexes of the place being assigned and its value, see `hoist_indexes`
+  hoisted: HashMap<NodeId, String>,
 }
 
-impl PythonVisitor {
-  pub fn new(indent: usize) -> Self {
-    Self { indent }
+impl<'a> PythonVisitor<'a> {
+  pub fn new(indent: usize, table: &'a SymbolTable, types: &'a HashMap<NodeId, DataType>, promotions: &'a HashMap<NodeId, Promotion>, overflow: Overflow, names: HashMap<SymbolId, String>) -> Self {
+    Self { indent, table, types, promotions, overflow, names, helpers: HashSet::new(), hoisted: HashMap::new() }
+  }
+
+  // Python ints never overflow, results of fixed width arithmetic are brought back
+  // into range or rejected depending on the overflow mode
+  fn fixed_width(&mut self, code: String, id: NodeId) -> String {
+    match self.types.get(&id) {
+      Some(DataType::Sized(int_type)) => {
+        let helper = match self.overflow {
+          Overflow::Wrap => "_r_wrap",
+          Overflow::Panic => "_r_check",
+        };
+        self.call_helper(helper, code, int_type)
+      }
+      _ => code,
+    }
+  }
+
+  fn call_helper(&mut self, helper: &'static str, code: String, int_type: &IntType) -> String {
+    self.helpers.insert(helper);
+    let signed = if int_type.signed() { "True" } else { "False" };
+    format!("{}({}, {}, {})", helper, code, int_type.bits(), signed)
+  }
+
+  // Name to emit for a symbol, names that aren't declared in the program are
+  // expected to come from python, so only keywords are escaped
+  fn name_of(&self, id: SymbolId, name: &str) -> String {
+    self.table.resolve(id)
+      .and_then(|declaration| self.names.get(&declaration))
+      .cloned()
+      .unwrap_or_else(|| escape_keyword(name))
+  }
+
+  fn name_in_scope(&self, scope_id: u32, name: &str) -> String {
+    match self.table.declaration_in(scope_id, name) {
+      Some(id) => self.name_of(id, name),
+      None => escape_keyword(name),
+    }
+  }
+
+  // Python unpacks tuples in assignments and for loops the same way
+  fn pattern(&self, pattern: &Pattern) -> String {
+    match pattern {
+      Pattern::Binding(name, id) => self.name_of(*id, name),
+      Pattern::Wildcard => "_".to_string(),
+      Pattern::Tuple(patterns) if patterns.len() == 1 => format!("({},)", self.pattern(&patterns[0])),
+      Pattern::Tuple(patterns) => {
+        let patterns = patterns.iter().map(|pattern| self.pattern(pattern)).collect::<Vec<String>>();
+        format!("({})", patterns.join(", "))
+      }
+    }
+  }
+
+  // Collects the bindings from outside the function `fn_scope` that are assigned in its body,
+  // and whether they belong to the module (global) or to an enclosing function (nonlocal)
+  fn outer_assignments(&self, body: &[Statement], fn_scope: u32, outer: &mut Vec<(SymbolId, bool)>) {
+    for statement in body {
+      match statement {
+        Statement::Assignment(target, _) | Statement::CompoundAssignment(target, _, _, _) => {
+          if let Some(declaration) = rebound(target).and_then(|reference| self.table.resolve(reference)) {
+            let declared_in = python_scope(self.table, self.table.symbol(declaration).scope);
+            if declared_in != fn_scope && !outer.iter().any(|(id, _)| *id == declaration) {
+              outer.push((declaration, declared_in == 0));
+            }
+          }
+        }
+        Statement::If(_, then_branch, else_branch, _) => {
+          self.outer_assignments(then_branch, fn_scope, outer);
+          if let Some(else_branch) = else_branch {
+            self.outer_assignments(std::slice::from_ref(&**else_branch), fn_scope, outer);
+          }
+        }
+        Statement::Loop(body, _) | Statement::For(_, _, body, _) => {
+          self.outer_assignments(body, fn_scope, outer);
+        }
+        // nested functions get their own declarations
+        _ => {}
+      }
+    }
+  }
+
+  // A place that's written more than once in the python code, like `a[f()] = _r_div(a[f()], 3)`,
+  // would call the functions in its indexes more than once. Those indexes are stored in
+  // temporaries first, named `_r_index0`, `_r_index1`... which no Ruston name can take
+  fn hoist_indexes(&mut self, place: &Expresion, code: &mut String) {
+    match place {
+      Expresion::Index(array, index, _) => {
+        self.hoist_indexes(array, code);
+        if has_call(index) {
+          let count = self.hoisted.values().filter(|name| name.starts_with("_r_index")).count();
+          let name = format!("_r_index{}", count);
+          code.push_str(&format!("{}{} = {}\n", " ".repeat(self.indent), name, index.accept(self)));
+          self.hoisted.insert(index.id(), name);
+        }
+      }
+      Expresion::TupleIndex(tuple, _, _) | Expresion::Member(tuple, _, _) => self.hoist_indexes(tuple, code),
+      _ => {}
+    }
+  }
+
+  // Rust evaluates the value of an assignment before the place, python evaluates the place first
+  // in augmented assignments and in the ones spelled out with helpers. When both call functions
+  // the value is stored in `_r_value` first
+  fn hoist_value(&mut self, place: &Expresion, value: &Expresion, code: &mut String) {
+    if has_call(place) && has_call(value) {
+      code.push_str(&format!("{}_r_value = {}\n", " ".repeat(self.indent), value.accept(self)));
+      self.hoisted.insert(value.id(), "_r_value".to_string());
+    }
+  }
+
+  // The code of an expression, or the temporary it was stored in
+  fn expression(&mut self, expr: &Expresion) -> String {
+    match self.hoisted.get(&expr.id()) {
+      Some(name) => name.clone(),
+      None => expr.accept(self),
+    }
+  }
+
+  // Python tuples are immutable, assigning to an element builds a new tuple
+  // and assigns it to the place that holds the old one
+  fn assign(&mut self, target: &Expresion, value: String) -> String {
+    match target {
+      Expresion::TupleIndex(tuple, index, _) => {
+        let tuple_code = tuple.accept(self);
+        let value = format!("{}[:{}] + ({},) + {}[{}:]", tuple_code, index, value, tuple_code, index + 1);
+        self.assign(tuple, value)
+      }
+      _ => format!("{}{} = {}", " ".repeat(self.indent), target.accept(self), value),
+    }
+  }
+
+  fn visit_block(&mut self, body: &[Statement]) -> String {
+    let mut code = String::new();
+    self.increment_indent();
+    for statement in body {
+      code.push_str(&statement.accept(self));
+      code.push('\n');
+    }
+    // python doesn't allow empty blocks
+    if body.is_empty() {
+      code.push_str(&format!("{}pass\n", " ".repeat(self.indent)));
+    }
+    self.decrement_indent();
+    code
   }
 
   fn increment_indent(&mut self) {
@@ -58,73 +238,108 @@ impl PythonVisitor {
 
 }
 
-impl Visitor for PythonVisitor {
+impl Visitor for PythonVisitor<'_> {
   fn visit_expression_statement(&mut self, expression: &Expresion) -> String {
     let exp = expression.accept(self);
     format!("{}{}", " ".repeat(self.indent), exp)
   }
 
-  fn visit_declaration(&mut self, name: &String, value: &Option<Expresion>) -> String {
+  fn visit_declaration(&mut self, name: &str, value: &Option<Expresion>, symbol: SymbolId) -> String {
+    let name = self.name_of(symbol, name);
     match value {
       Some(expr) => format!("{}{} = {}", " ".repeat(self.indent), name, expr.accept(self)),
       None => format!("{}{} = None", " ".repeat(self.indent), name),
     }
   }
 
+  fn visit_destructure(&mut self, pattern: &Pattern, value: &Expresion) -> String {
+    format!("{}{} = {}", " ".repeat(self.indent), self.pattern(pattern), value.accept(self))
+  }
+
   fn visit_assignment(&mut self, left: &Expresion, right: &Expresion) -> String {
-    format!("{}{} = {}", " ".repeat(self.indent), left.accept(self), right.accept(self))
+    let mut code = String::new();
+    if let Expresion::TupleIndex(..) = left {
+      self.hoist_value(left, right, &mut code);
+      self.hoist_indexes(left, &mut code);
+    }
+    let value = self.expression(right);
+    code.push_str(&self.assign(left, value));
+    self.hoisted.clear();
+    code
   }
 
-  fn visit_if(&mut self, condition: &Expresion, then_branch: &Vec<Statement>, else_branch: &Option<Box<Statement>>, _: u32) -> String {
-    let mut code = format!("{}if {}:\n", " ".repeat(self.indent), condition.accept(self));
-    self.increment_indent();
-    for statement in then_branch {
-      code.push_str(&statement.accept(self));
-      code.push('\n');
+  fn visit_compound_assignment(&mut self, left: &Expresion, operator: &Token, right: &Expresion, id: NodeId) -> String {
+    // python's augmented assignment only does the same as rust when no runtime helper is involved
+    let plain = match self.types.get(&id) {
+      Some(DataType::Sized(_)) => matches!(operator.to_string().as_str(), "&" | "|" | "^"),
+      Some(DataType::Integer) => !matches!(operator.to_string().as_str(), "/" | "%"),
+      _ => true,
+    };
+    let mut code = String::new();
+    self.hoist_value(left, right, &mut code);
+    if plain && !matches!(left, Expresion::TupleIndex(..)) {
+      let value = self.expression(right);
+      code.push_str(&format!("{}{} {}= {}", " ".repeat(self.indent), left.accept(self), to_python_operator(operator), value));
+      self.hoisted.clear();
+      return code;
     }
-    self.decrement_indent();
+    self.hoist_indexes(left, &mut code);
+    let value = self.visit_binary(left, operator, right, id);
+    code.push_str(&self.assign(left, value));
+    self.hoisted.clear();
+    code
+  }
+
+  fn visit_if(&mut self, condition: &Expresion, then_branch: &[Statement], else_branch: &Option<Box<Statement>>, _: u32) -> String {
+    let mut code = format!("{}if {}:\n", " ".repeat(self.indent), condition.accept(self));
+    code.push_str(&self.visit_block(then_branch));
     // else bramch can be else or else if
     if let Some(else_branch) = else_branch {
       code.push_str(&format!("{}else:\n", " ".repeat(self.indent)));
-      self.increment_indent();
-      code.push_str(&else_branch.accept(self));
-      self.decrement_indent();
+      match &**else_branch {
+        // a plain else is stored as an `if true` with its own scope
+        Statement::If(_, body, None, scope_id) if self.table.scope(*scope_id).kind == ScopeKind::Else => {
+          code.push_str(&self.visit_block(body));
+        }
+        _ => {
+          self.increment_indent();
+          code.push_str(&else_branch.accept(self));
+          self.decrement_indent();
+        }
+      }
     }
     code
    
   }
 
-  fn visit_loop(&mut self, body: &Vec<Statement>, scope_id: u32) -> String {
+  fn visit_loop(&mut self, body: &[Statement], scope_id: u32) -> String {
     let mut code = format!("{}while True:\n", " ".repeat(self.indent));
-    self.increment_indent();
-    for statement in body {
-      code.push_str(&statement.accept(self));
-      code.push('\n');
-    }
-    self.decrement_indent();
+    code.push_str(&self.visit_block(body));
     code
   }
 
-  fn visit_for(&mut self, variable: &String, iterable: &Expresion, body: &Vec<Statement>, scope_id: u32) -> String {
-    let mut code = format!("{}for {} in {}:\n", " ".repeat(self.indent), variable, iterable.accept(self));
-    self.increment_indent();
-    for statement in body {
-      code.push_str(&statement.accept(self));
-      code.push('\n');
-    }
-    self.decrement_indent();
+  fn visit_for(&mut self, pattern: &Pattern, iterable: &Expresion, body: &[Statement], _scope_id: u32) -> String {
+    let mut code = format!("{}for {} in {}:\n", " ".repeat(self.indent), self.pattern(pattern), iterable.accept(self));
+    code.push_str(&self.visit_block(body));
     code
    
   }
 
-  fn visit_fn_declaration(&mut self, name: &String, params: &Vec<String>, body: &Vec<Statement>, _: u32) -> String {
+  fn visit_fn_declaration(&mut self, name: &str, params: &[String], body: &[Statement], scope_id: u32) -> String {
+    let name = match self.table.function(scope_id) {
+      Some(function) => self.name_of(function, name),
+      None => escape_keyword(name),
+    };
+    let params = params.iter().map(|param| self.name_in_scope(scope_id, param)).collect::<Vec<String>>();
     let mut code = format!("{}def {}({}):\n", " ".repeat(self.indent), name, params.join(", "));
-    self.increment_indent();
-    for statement in body {
-      code.push_str(&statement.accept(self));
-      code.push('\n');
+    // assigning to an outer binding would create a new local in python
+    let mut outer = Vec::new();
+    self.outer_assignments(body, scope_id, &mut outer);
+    for (declaration, is_global) in outer {
+      let keyword = if is_global { "global" } else { "nonlocal" };
+      code.push_str(&format!("{}{} {}\n", " ".repeat(self.indent + 4), keyword, self.names[&declaration]));
     }
-    self.decrement_indent();
+    code.push_str(&self.visit_block(body));
     code
 
   }
@@ -137,40 +352,116 @@ impl Visitor for PythonVisitor {
   
   }
 
+  fn visit_break(&mut self) -> String {
+    format!("{}break", " ".repeat(self.indent))
+  }
+
   fn visit_literal(&mut self, literal: &Literal) -> String {
     match literal {
       Literal::Number(number) => self.visit_number(number),
       Literal::String(string) => self.visit_string(string),
       Literal::Boolean(boolean) => self.visit_boolean(boolean),
+      Literal::Char(char) => self.visit_char(*char),
     }
     
   }
 
-  fn visit_identifier(&mut self, identifier: &String) -> String {
-    identifier.clone()
+  fn visit_identifier(&mut self, identifier: &str, reference: SymbolId) -> String {
+    self.name_of(reference, identifier)
   }
 
-  fn visit_binary(&mut self, left: &Expresion, operator: &Token, right: &Expresion) -> String {
-    let left = left.accept(self);
-    let right = right.accept(self);
-    format!("({} {} {})", left,to_python_operator(operator), right)
+  fn visit_binary(&mut self, left: &Expresion, operator: &Token, right: &Expresion, id: NodeId) -> String {
+    // `**` binds tighter than unary minus in python, `-2 ** 2` would be `-(2 ** 2)`
+    let negative = match left {
+      Expresion::Unary(..) => true,
+      Expresion::Literal(Literal::Number(number), _) => number.to_string().starts_with('-'),
+      _ => false,
+    };
+    let left = if negative && operator.to_string() == "**" {
+      format!("({})", left.accept(self))
+    } else {
+      left.accept(self)
+    };
+    let right = self.expression(right);
+    // python mixes ints and floats on its own, the conversion is spelled out to match the checker
+    let (left, mut right) = match self.promotions.get(&id) {
+      Some(Promotion::Left) => (format!("float({})", left), right),
+      Some(Promotion::Right) => (left, format!("float({})", right)),
+      None => (left, right),
+    };
+    let sized = match self.types.get(&id) {
+      Some(DataType::Sized(int_type)) => Some(*int_type),
+      _ => None,
+    };
+    // shifting a fixed width integer by its width or more overflows
+    if let (Some(int_type), "<<" | ">>") = (sized, operator.to_string().as_str()) {
+      right = match self.overflow {
+        Overflow::Wrap => format!("({} & {})", right, int_type.bits() - 1),
+        Overflow::Panic => {
+          self.helpers.insert("_r_shift");
+          format!("_r_shift({}, {})", right, int_type.bits())
+        }
+      };
+    }
+    // python floors integer division and remainder, rust truncates them toward zero. A dynamic
+    // result means neither side is a float, so it's taken as an integer division too
+    let helper = match (operator.to_string().as_str(), self.types.get(&id)) {
+      ("/", Some(DataType::Integer | DataType::Sized(_) | DataType::Dynamic)) => Some("_r_div"),
+      ("%", Some(DataType::Integer | DataType::Sized(_) | DataType::Dynamic)) => Some("_r_rem"),
+      _ => None,
+    };
+    let code = match helper {
+      Some(helper) => {
+        self.helpers.insert(helper);
+        if helper == "_r_rem" {
+          self.helpers.insert("_r_div");
+        }
+        format!("{}({}, {})", helper, left, right)
+      }
+      None => format!("({} {} {})", left,to_python_operator(operator), right),
+    };
+    match (operator.to_string().as_str(), sized) {
+      // these can't leave the range of their operands
+      ("&" | "|" | "^" | ">>", _) => code,
+      // the bits shifted out are dropped
+      ("<<", Some(int_type)) => self.call_helper("_r_wrap", code, &int_type),
+      _ => self.fixed_width(code, id),
+    }
   
   }
-  fn visit_fn_call(&mut self, name: &String, args: &Vec<Expresion>) -> String {
+  fn visit_fn_call(&mut self, name: &str, args: &[Expresion], reference: SymbolId) -> String {
+    let name = self.name_of(reference, name);
     let args = args.iter().map(|arg| arg.accept(self)).collect::<Vec<String>>().join(", ");
     format!("{}({})", name, args)
    
   }
 
-  fn visit_array(&mut self, elements: &Vec<Expresion>) -> String {
+  fn visit_array(&mut self, elements: &[Expresion]) -> String {
     let elements = elements.iter().map(|element| element.accept(self)).collect::<Vec<String>>().join(", ");
     format!("[{}]", elements)
   
   }
 
-  fn visit_unary(&mut self, operator: &Token, operand: &Expresion) -> String {
+  fn visit_unary(&mut self, operator: &Token, operand: &Expresion, id: NodeId) -> String {
     let operand = operand.accept(self);
-    format!("{}{}",to_python_operator(operator), operand)
+    let data_type = self.types.get(&id).cloned();
+    match operator.to_string().as_str() {
+      // `!` on integers is a bitwise not, the result of a bitwise not only leaves
+      // the range of unsigned types
+      "!" | "~" if matches!(data_type, Some(DataType::Integer | DataType::Sized(_))) => {
+        let code = format!("~{}", operand);
+        match data_type {
+          Some(DataType::Sized(int_type)) if !int_type.signed() => self.call_helper("_r_wrap", code, &int_type),
+          _ => code,
+        }
+      }
+      // `not` binds looser than comparisons in python
+      "!" => format!("(not {})", operand),
+      _ => {
+        let code = format!("{}{}",to_python_operator(operator), operand);
+        self.fixed_width(code, id)
+      }
+    }
 
   }
 
@@ -189,38 +480,239 @@ impl Visitor for PythonVisitor {
   fn visit_number(&mut self, number: &Number) -> String {
     match number {
       Number::Integer(value) => value.to_string(),
-      Number::Float(value) => value.to_string(),
+      // python doesn't take `_` everywhere ruston does
+      Number::Float(_, text) => text.replace('_', ""),
     }
   
   }
 
-  fn visit_string(&mut self, string: &String) -> String {
+  fn visit_string(&mut self, string: &str) -> String {
     format!("\"{}\"", string)
   }
 
   fn visit_boolean(&mut self, boolean: &bool) -> String {
-    boolean.to_string()
+    if *boolean { "True".to_string() } else { "False".to_string() }
+  }
+
+  // chars are strings of length one in python
+  fn visit_char(&mut self, char: char) -> String {
+    let escaped = match char {
+      '"' => "\\\"".to_string(),
+      '\\' => "\\\\".to_string(),
+      '\n' => "\\n".to_string(),
+      '\t' => "\\t".to_string(),
+      '\r' => "\\r".to_string(),
+      char if char.is_control() => format!("\\x{:02x}", char as u32),
+      char => char.to_string(),
+    };
+    format!("\"{}\"", escaped)
   }
   
-  fn visit_tuple(&mut self, elements: &Vec<Expresion>) -> String {
-    let elements = elements.iter().map(|element| element.accept(self)).collect::<Vec<String>>().join(", ");
-    format!("({})", elements)
+  fn visit_tuple(&mut self, elements: &[Expresion]) -> String {
+    let elements = elements.iter().map(|element| element.accept(self)).collect::<Vec<String>>();
+    // a single element tuple needs its trailing comma in python
+    if elements.len() == 1 {
+      return format!("({},)", elements[0]);
+    }
+    format!("({})", elements.join(", "))
     }
   
   fn visit_index(&mut self, array: &Expresion, index: &Expresion) -> String {
-    format!("{}[{}]", array.accept(self), index.accept(self))
+    let index = self.expression(index);
+    format!("{}[{}]", array.accept(self), index)
     }
   
-  fn visit_member(&mut self, object: &Expresion, member: &String) -> String {
-    format!("{}.{}", object.accept(self), member)
+  fn visit_member(&mut self, object: &Expresion, member: &str) -> String {
+    // attributes can't shadow builtins, only keywords need escaping
+    format!("{}.{}", object.accept(self), escape_keyword(member))
     }
   
   fn visit_tuple_index(&mut self, tuple: &Expresion, index: usize) -> String {
       format!("{}[{}]", tuple.accept(self), index)
     }
+
+  fn visit_cast(&mut self, expr: &Expresion, data_type: &DataType) -> String {
+    let from = self.types.get(&expr.id());
+    let expr = expr.accept(self);
+    match (from, data_type) {
+      (Some(DataType::Char), DataType::Char | DataType::String) => expr,
+      (Some(DataType::Char), DataType::Integer) => format!("ord({})", expr),
+      (Some(DataType::Char), DataType::Sized(int_type)) => self.call_helper("_r_wrap", format!("ord({})", expr), int_type),
+      (_, DataType::Char) => format!("chr({})", expr),
+      (Some(DataType::Float), DataType::Float) => expr,
+      (_, DataType::Float) => format!("float({})", expr),
+      // floats saturate at the bounds of the target type, like in rust
+      (Some(DataType::Float), DataType::Sized(int_type)) => self.call_helper("_r_saturate", expr, int_type),
+      (Some(DataType::Float | DataType::Boolean), DataType::Integer | DataType::Sized(_)) => format!("int({})", expr),
+      (Some(DataType::Dynamic), DataType::Integer) => format!("int({})", expr),
+      (Some(DataType::Dynamic), DataType::Boolean) => format!("bool({})", expr),
+      // integer casts truncate to the target type in every overflow mode
+      (_, DataType::Sized(int_type)) => self.call_helper("_r_wrap", expr, int_type),
+      (Some(DataType::Boolean), DataType::String) => format!("(\"true\" if {} else \"false\")", expr),
+      (Some(DataType::String), DataType::String) => expr,
+      (_, DataType::String) => format!("str({})", expr),
+      _ => expr,
+    }
+  }
+}
+
+
+
+// Python functions emitted before the program when it needs them, in dependency order
+const RUNTIME: &[(&str, &str)] = &[
+  ("_r_div", "def _r_div(a, b):\n    q = abs(a) // abs(b)\n    return q if (a < 0) == (b < 0) else -q\n"),
+  ("_r_rem", "def _r_rem(a, b):\n    return a - b * _r_div(a, b)\n"),
+  ("_r_wrap", "def _r_wrap(value, bits, signed):\n    value &= (1 << bits) - 1\n    if signed and value >> (bits - 1):\n        value -= 1 << bits\n    return value\n"),
+  ("_r_shift", "def _r_shift(amount, bits):\n    if not 0 <= amount < bits:\n        raise OverflowError(\"attempt to shift by {} with overflow\".format(amount))\n    return amount\n"),
+  ("_r_saturate", "def _r_saturate(value, bits, signed):\n    if value != value:\n        return 0\n    low, high = (-(1 << (bits - 1)), (1 << (bits - 1)) - 1) if signed else (0, (1 << bits) - 1)\n    return int(min(max(value, low), high))\n"),
+  ("_r_check", "def _r_check(value, bits, signed):\n    low, high = (-(1 << (bits - 1)), (1 << (bits - 1)) - 1) if signed else (0, (1 << bits) - 1)\n    if not low <= value <= high:\n        raise OverflowError(\"attempt to compute {} with overflow for {}{}\".format(value, \"i\" if signed else \"u\", bits))\n    return value\n"),
+];
+
+const PYTHON_KEYWORDS: &[&str] = &[
+  "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class",
+  "continue", "def", "del", "elif", "else", "except", "finally", "for", "from", "global",
+  "if", "import", "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise",
+  "return", "try", "while", "with", "yield",
+];
+
+const PYTHON_BUILTINS: &[&str] = &[
+  "abs", "aiter", "all", "anext", "any", "ascii", "bin", "bool", "breakpoint", "bytearray",
+  "bytes", "callable", "chr", "classmethod", "compile", "complex", "copyright", "credits",
+  "delattr", "dict", "dir", "divmod", "enumerate", "eval", "exec", "exit", "filter", "float",
+  "format", "frozenset", "getattr", "globals", "hasattr", "hash", "help", "hex", "id", "input",
+  "int", "isinstance", "issubclass", "iter", "len", "license", "list", "locals", "map", "max",
+  "memoryview", "min", "next", "object", "oct", "open", "ord", "pow", "print", "property",
+  "quit", "range", "repr", "reversed", "round", "set", "setattr", "slice", "sorted",
+  "staticmethod", "str", "sum", "super", "tuple", "type", "vars", "zip",
+];
+
+// Whether evaluating the expression may call a function
+fn has_call(expr: &Expresion) -> bool {
+  match expr {
+    Expresion::FnCall(..) => true,
+    Expresion::Literal(..) | Expresion::Identifier(..) => false,
+    Expresion::Binary(left, _, right, _) | Expresion::Index(left, right, _) | Expresion::Range(left, right, _, _) => has_call(left) || has_call(right),
+    Expresion::Tuple(elements, _) | Expresion::Array(elements, _) => elements.iter().any(has_call),
+    Expresion::Member(expr, _, _) | Expresion::TupleIndex(expr, _, _) | Expresion::Unary(_, expr, _) | Expresion::Cast(expr, _, _) => has_call(expr),
+  }
+}
+
+// Ruston names that are python keywords get a trailing underscore (`class` -> `class_`)
+fn escape_keyword(name: &str) -> String {
+  if PYTHON_KEYWORDS.contains(&name) {
+    format!("{}_", name)
+  } else {
+    name.to_string()
+  }
+}
+
+// Names declared in Ruston are also kept from shadowing python builtins the same way
+// (`list` -> `list_`, and `_r_div` -> `_r_div_`), the mapping doesn't depend on the rest of the program so python
+// code importing Ruston functions can rely on it.
+// Python reads identifiers in NFKC, names that only differ there (`ﬁ` and `fi`) are
+// the same python name and get told apart like any other clash
+fn python_identifier(name: &str) -> String {
+  let name: String = name.nfkc().collect();
+  // `_r_` starts the names of the runtime helpers and temporaries
+  if PYTHON_BUILTINS.contains(&name.as_str()) || name.starts_with("_r_") {
+    format!("{}_", name)
+  } else {
+    escape_keyword(&name)
+  }
+}
+
+// Python only has function level scoping, so a binding made in a nested Ruston block
+// would overwrite any other binding with the same name in the enclosing function.
+// Every declaration gets a name that is unique in the Python function (or module) it
+// ends up in: the first binding keeps its name and later ones are renamed `x_1`, `x_2`...
+fn python_names(table: &SymbolTable) -> HashMap<SymbolId, String> {
+  let mut names: HashMap<SymbolId, String> = HashMap::new();
+
+  // Names that come from python itself or from the runtime helpers must never be shadowed
+  let external: HashSet<String> = table.symbols.iter()
+    .filter(|symbol| matches!(symbol.kind, SymbolKind::Reference { declaration: None }))
+    .map(|symbol| symbol.value.clone())
+    .chain(RUNTIME.iter().map(|(name, _)| name.to_string()))
+    .collect();
+
+  // Parents are created before their children, so outer names are always known first
+  for scope in table.scopes.iter().filter(|scope| python_scope(table, scope.id) == scope.id) {
+    let mut used = external.clone();
+
+    // Outer bindings used from this function (or from functions nested in it)
+    // would be hidden by a local with the same name
+    for symbol in &table.symbols {
+      if let SymbolKind::Reference { declaration: Some(declaration) } = symbol.kind {
+        let declared_in = python_scope(table, table.symbol(declaration).scope);
+        if crosses(table, python_scope(table, symbol.scope), declared_in, scope.id) {
+          if let Some(name) = names.get(&declaration) {
+            used.insert(name.clone());
+          }
+        }
+      }
+    }
+
+    // The first binding of each name keeps it, so names written in the source win over generated ones
+    let mut renamed = Vec::new();
+    for symbol in &table.symbols {
+      if let UseType::Declaration = symbol.use_type {
+        if python_scope(table, symbol.scope) == scope.id {
+          let name = python_identifier(&symbol.value);
+          if used.insert(name.clone()) {
+            names.insert(symbol.id, name);
+          } else {
+            renamed.push((symbol, name));
+          }
+        }
+      }
+    }
+    for (symbol, base) in renamed {
+      let mut suffix = 1;
+      let mut name = format!("{}_{}", base, suffix);
+      while used.contains(&name) {
+        suffix += 1;
+        name = format!("{}_{}", base, suffix);
+      }
+      used.insert(name.clone());
+      names.insert(symbol.id, name);
+    }
+  }
+  names
+}
+
+// The variable an assignment to `target` binds again in python, tuple elements
+// are assigned by rebinding the whole tuple
+fn rebound(target: &Expresion) -> Option<SymbolId> {
+  match target {
+    Expresion::Identifier(_, reference, _) => Some(*reference),
+    Expresion::TupleIndex(tuple, _, _) => rebound(tuple),
+    _ => None,
+  }
 }
 
+// The python scope of a ruston scope is its enclosing function, or the module (scope 0)
+fn python_scope(table: &SymbolTable, scope_id: u32) -> u32 {
+  std::iter::once(scope_id)
+    .chain(table.ancestors(scope_id))
+    .find(|&id| table.function(id).is_some())
+    .unwrap_or(0)
+}
 
+// Whether `scope` is between a use in python scope `used_in` and the python scope
+// `declared_in` of its declaration, not counting the latter
+fn crosses(table: &SymbolTable, used_in: u32, declared_in: u32, scope: u32) -> bool {
+  let mut current = used_in;
+  while current != declared_in {
+    if current == scope {
+      return true;
+    }
+    match table.scope(current).parent {
+      Some(parent) => current = python_scope(table, parent),
+      None => return false,
+    }
+  }
+  false
+}
 
 fn to_python_operator(operator: &Token) -> String {
   match operator.to_string().as_str() {
@@ -1,17 +1,21 @@
 use std::fmt:: Display;
-use std::str::Chars;
+use std::str::CharIndices;
 use std::iter::Peekable;
-#[derive(Debug, PartialEq, PartialOrd,Clone)]
+use unicode_normalization::UnicodeNormalization;
+use unicode_xid::UnicodeXID;
+#[derive(Debug, PartialEq,Clone)]
 pub enum Number {
-    Float(f32),
-    Integer(i32),
+    // floats keep the literal as written, so codegen doesn't depend on how the value prints
+    Float(f64, String),
+    // wide enough for every value of the fixed width types, u64 included
+    Integer(i128),
 }
 
 
 impl Display for Number {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Number::Float(value) => write!(f, "{}", value),
+            Number::Float(_, text) => write!(f, "{}", text),
             Number::Integer(value) => write!(f, "{}", value),
         }
     }
@@ -19,13 +23,19 @@ impl Display for Number {
 
 impl Eq for Number {}
 
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Ord for Number {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         match (self, other) {
-            (Number::Float(a), Number::Float(b)) => a.partial_cmp(b).unwrap(),
+            (Number::Float(a, _), Number::Float(b, _)) => a.total_cmp(b),
             (Number::Integer(a), Number::Integer(b)) => a.cmp(b),
-            (Number::Float(_), Number::Integer(_)) => std::cmp::Ordering::Greater,
-            (Number::Integer(_), Number::Float(_)) => std::cmp::Ordering::Less,
+            (Number::Float(..), Number::Integer(_)) => std::cmp::Ordering::Greater,
+            (Number::Integer(_), Number::Float(..)) => std::cmp::Ordering::Less,
         }
     }
 }
@@ -39,16 +49,19 @@ pub enum Token {
     Identifier(String),
     Number(Number),
     String(String),
+    Char(char),
     For,
     In,
     Loop,
     Let,
     Const,
     Return,
-    Operator(String),          // +, -, *, /, %, **
+    Break,
+    As,
+    Operator(String),          // +, -, *, /, %, **, &, |, ^, <<, >>, ~, !
     LogicalOperator(String), // &&, ||, !, ==, !=, <, >, <=, >=
     Equal,
-    // CompoundEqual(String), // +=, -=, *=, /=, %=
+    CompoundEqual(String), // +=, -=, *=, /=, %=, **=, &=, |=, ^=, <<=, >>=, holds the operator without '='
     LeftParen,    // (
     RightParen,   // )
     LeftBrace,    // {
@@ -59,11 +72,13 @@ pub enum Token {
     Semicolon,    // ;
     Colon,        // :
     Dot,          // .
+    Hash,         // #, starts an attribute
     EOF,
     TypeInt, // datatype
     TypeString, // datatype
     TypeFloat, // datatype
     TypeBool, // datatype
+    TypeChar, // datatype
     True ,
     False,
     ArrowType, // ->
@@ -87,14 +102,18 @@ impl  Display for Token {
             Token::Identifier(value) => write!(f, "{}", value),
             Token::Number(value) => write!(f, "{}", value),
             Token::String(value) => write!(f, "{}", value),
+            Token::Char(value) => write!(f, "{:?}", value),
             Token::For => write!(f, "for"),
             Token::Loop => write!(f, "loop"),
             Token::Let => write!(f, "let"),
             Token::Const => write!(f, "const"),
             Token::Return => write!(f, "return"),
+            Token::Break => write!(f, "break"),
+            Token::As => write!(f, "as"),
             Token::Operator(value) => write!(f, "{}", value),
             Token::LogicalOperator(value) => write!(f, "{}", value),
             Token::Equal => write!(f, "="),
+            Token::CompoundEqual(value) => write!(f, "{}=", value),
             Token::LeftParen => write!(f, "("),
             Token::RightParen => write!(f, ")"),
             Token::LeftBrace => write!(f, "{{"),
@@ -105,11 +124,13 @@ impl  Display for Token {
             Token::Semicolon => write!(f, ";"),
             Token::Colon => write!(f, ":"),
             Token::Dot => write!(f, "."),
+            Token::Hash => write!(f, "#"),
             Token::EOF => write!(f, "EOF"),
             Token::TypeInt => write!(f, "int"),
             Token::TypeString => write!(f, "string"),
             Token::TypeFloat => write!(f, "float"),
             Token::TypeBool => write!(f, "bool"),
+            Token::TypeChar => write!(f, "char"),
         }
     }
 }
@@ -117,45 +138,34 @@ impl  Display for Token {
 
 
 
-#[derive(Debug)]
-pub struct LexerState {
-    current: usize,
-    line: usize,
-    col: usize,
-    lookahead: Option<Token>,
-    current_char: Option<char>,
-}
-
-
-
-
-
-
-
-
 #[derive(Debug, Clone)]
 pub struct Lexer<'a> {
     source: &'a str,
-    current: usize,
+    current: usize, // byte offset of current_char in source
     line: usize,
-    col: usize,
+    col: usize, // counted in chars, not bytes
     lookahead: Option<Token>,
+    // line where the last token given to the parser ends, and where the lookahead does
+    token_line: usize,
+    lookahead_line: usize,
     current_char: Option<char>,
     // peekable
-    iter_char: Peekable<Chars<'a>>,
+    iter_char: Peekable<CharIndices<'a>>,
 
 }
 
 impl<'a> Lexer<'a> {
-    pub fn new(source: &str) -> Lexer {
+    pub fn new(source: &'a str) -> Lexer<'a> {
         Lexer {
             source,
             current: 0,
             col: 1,  // column
             line: 1, // row
             lookahead: None,
+            token_line: 1,
+            lookahead_line: 1,
             current_char: None,
-            iter_char: source.chars().peekable(),
+            iter_char: source.char_indices().peekable(),
         }
     }
 
@@ -163,59 +173,68 @@ impl<'a> Lexer<'a> {
         (self.line, self.col)
     }
 
-    pub fn save_position(&self) -> LexerState {
-        LexerState {
-            current: self.current,
-            current_char: self.current_char,
-            line: self.line,
-            col: self.col,
-            lookahead: self.lookahead.clone(),
-        }
-    }
-
-    pub fn restore_position(&mut self, state: LexerState) {
-        self.current = state.current;
-        self.line = state.line;
-        self.col = state.col;
-        self.lookahead = state.lookahead;
-        self.current_char = state.current_char;
-
-        self.iter_char = self.source[self.current..].chars().peekable();
+    // Unlike the current position, it doesn't move when the next token is peeked
+    pub fn token_line(&self) -> usize {
+        self.token_line
     }
 
-
-
     fn advance(&mut self) -> Option<char> {
-        self.current += 1;
-        self.col += 1;
-
-        if let Some('\n') = self.current_char {
-            self.line += 1;
-            self.col = 1;
+        // the position moves past the char that's left behind
+        match self.current_char {
+            Some('\n') => {
+                self.line += 1;
+                self.col = 1;
+            }
+            Some(_) => self.col += 1,
+            None => {}
         }
 
-        self.current_char = self.iter_char.next();
+        match self.iter_char.next() {
+            Some((offset, c)) => {
+                self.current = offset;
+                self.current_char = Some(c);
+            }
+            None => {
+                self.current = self.source.len();
+                self.current_char = None;
+            }
+        }
         self.current_char
     }
 
     fn peek(&mut self) -> Option<char> {
-        self.iter_char.peek().cloned()
+        self.iter_char.peek().map(|(_, c)| *c)
     }
 
     fn scan_number(&mut self) -> Number {
+        let (line, col) = (self.line, self.col);
         let mut number = String::new();
+        // floats are kept as written, `_` included
+        let mut text = String::new();
         let mut is_float = false;
 
-        if let Some('-') = self.current_char {
-            number.push('-');
+        // 0x, 0b and 0o prefixes are only for integers
+        let radix = match (self.current_char, self.peek()) {
+            (Some('0'), Some('x')) => 16,
+            (Some('0'), Some('o')) => 8,
+            (Some('0'), Some('b')) => 2,
+            _ => 10,
+        };
+        if radix != 10 {
+            self.advance();
             self.advance();
         }
 
+        // '_' separates digits, it doesn't end up in the value
         while let Some(c) = self.current_char {
-            if c.is_digit(10) {
+            if c.is_digit(radix) {
             number.push(c);
+            text.push(c);
             self.advance();
-            } else if c == '.' {
+            } else if c == '_' {
+            text.push(c);
+            self.advance();
+            } else if c == '.' && radix == 10 {
             if is_float {
                 break; // second dot found, stop parsing
             }
@@ -224,54 +243,70 @@ impl<'a> Lexer<'a> {
             }
             is_float = true;
             number.push(c);
+            text.push(c);
             self.advance();
             } else {
             break;
             }
         }
 
-        if let Some('e') | Some('E') = self.current_char {
+        if radix == 10 && matches!(self.current_char, Some('e') | Some('E')) {
             is_float = true;
             number.push('e');
+            text.push(self.current_char.unwrap());
             self.advance();
 
             if let Some('-') | Some('+') = self.current_char {
                 number.push(self.current_char.unwrap());
+                text.push(self.current_char.unwrap());
                 self.advance();
             }
 
+            let exponent = number.len();
             while let Some(c) = self.current_char {
-                if c.is_digit(10) {
+                if c.is_ascii_digit() {
                     number.push(c);
+                    text.push(c);
+                    self.advance();
+                } else if c == '_' {
+                    text.push(c);
                     self.advance();
                 } else {
                     break;
                 }
             }
+            if number.len() == exponent {
+                eprintln!("Error: Malformed float literal {}, the exponent has no digits at line: {}, col: {}", text, line, col);
+                std::process::exit(1);
+            }
         }
 
-        // Check if the next character is alphanumeric, which would make the number invalid
-        if let Some(c) = self.current_char {
-            if c.is_alphanumeric() {
-                eprintln!(
-                    "Error: Invalid number format at line: {}, col: {}",
-                    self.line, self.col
-                );
-                // skip the invalid number
-                while let Some(c) = self.current_char {
-                    if !c.is_alphanumeric() {
-                        break;
-                    }
-                    self.advance();
-                }
-                return Number::Integer(0);
-            }
+        // Check if the next character is alphanumeric, which would make the number invalid,
+        // a prefix without digits (`0x`) is invalid as well
+        if self.current_char.is_some_and(|c| c.is_alphanumeric()) || number.is_empty() {
+            eprintln!(
+                "Error: Invalid number format at line: {}, col: {}",
+                self.line, self.col
+            );
+            std::process::exit(1);
         }
 
         if is_float {
-            Number::Float(number.parse().unwrap())
+            match number.parse::<f64>() {
+                Ok(value) if value.is_finite() => Number::Float(value, text),
+                _ => {
+                    eprintln!("Error: Float literal {} out of range at line: {}, col: {}", number, line, col);
+                    std::process::exit(1);
+                }
+            }
         } else {
-            Number::Integer(number.parse().unwrap())
+            match i128::from_str_radix(&number, radix) {
+                Ok(value) => Number::Integer(value),
+                Err(_) => {
+                    eprintln!("Error: Integer literal {} out of range at line: {}, col: {}", number, line, col);
+                    std::process::exit(1);
+                }
+            }
         }
     }
 
@@ -279,13 +314,14 @@ impl<'a> Lexer<'a> {
         let mut string = String::new();
         let mut is_closed = false;
         self.advance(); // consume the opening quote
+        let start = self.current;
         while let Some(c) = self.current_char {
             if c == '"' {
+                string.push_str(&self.source[start..self.current]);
                 self.advance(); // consume the closing quote
                 is_closed = true;
                 break;
             } else {
-                string.push(c);
                 self.advance();
             }
         }
@@ -300,29 +336,100 @@ impl<'a> Lexer<'a> {
         string
     }
 
+    // A single character between quotes, `'a'` or an escape like `'\n'` and `'\u{1F600}'`
+    fn scan_char(&mut self) -> char {
+        let (line, col) = (self.line, self.col);
+        self.advance(); // consume the opening quote
+        let value = match self.current_char {
+            Some('\\') => {
+                self.advance();
+                self.scan_escape(line, col)
+            }
+            Some('\'') | Some('\n') | None => {
+                eprintln!("Error: Empty character literal at line: {}, col: {}", line, col);
+                std::process::exit(1);
+            }
+            Some(c) => {
+                self.advance();
+                c
+            }
+        };
+        if self.current_char != Some('\'') {
+            eprintln!("Error: Character literal must hold exactly one character at line: {}, col: {}", line, col);
+            std::process::exit(1);
+        }
+        self.advance(); // consume the closing quote
+        value
+    }
+
+    // The character an escape stands for, the backslash is already consumed
+    fn scan_escape(&mut self, line: usize, col: usize) -> char {
+        let escape = self.current_char;
+        self.advance();
+        let value = match escape {
+            Some('n') => Some('\n'),
+            Some('t') => Some('\t'),
+            Some('r') => Some('\r'),
+            Some('0') => Some('\0'),
+            Some('\\') => Some('\\'),
+            Some('\'') => Some('\''),
+            Some('"') => Some('"'),
+            Some('u') if self.current_char == Some('{') => {
+                self.advance();
+                let mut digits = String::new();
+                while let Some(c) = self.current_char {
+                    if c == '}' {
+                        break;
+                    }
+                    digits.push(c);
+                    self.advance();
+                }
+                if self.current_char == Some('}') {
+                    self.advance();
+                    u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        };
+        match value {
+            Some(value) => value,
+            None => {
+                eprintln!("Error: Invalid escape in character literal at line: {}, col: {}", line, col);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // Identifiers are `XID_Start XID_Continue*` (or start with `_`), normalized to NFC
+    // so the different encodings of a name are the same identifier
     fn scan_identifier_keyword(&mut self) -> String {
-        let mut identifier = String::new();
+        let start = self.current;
         while let Some(c) = self.current_char {
-            if c.is_alphanumeric() || c == '_' {
-                identifier.push(c);
+            if c.is_xid_continue() {
                 self.advance();
             } else {
                 break;
             }
         }
-        identifier
+        self.source[start..self.current].nfc().collect()
     }
 
     pub fn get_next_token(&mut self) -> Token {
-
-        if  self.current_char.is_none() {
-            self.advance();
+        if let Some(token) = self.lookahead.take() {
+            self.token_line = self.lookahead_line;
+            return token;
         }
+        let token = self.scan_token();
+        self.token_line = self.line;
+        token
+    }
 
+    fn scan_token(&mut self) -> Token {
 
-
-        if let Some(token) = self.lookahead.take() {
-            return token;
+        if  self.current_char.is_none() {
+            self.advance();
         }
 
         
@@ -370,6 +477,10 @@ impl<'a> Lexer<'a> {
                         self.advance();
                         return Token::Semicolon;
                     }
+                    '#' => {
+                        self.advance();
+                        return Token::Hash;
+                    }
                     '.' => {
                         // print current
                         if self.peek() == Some('.') {
@@ -423,23 +534,26 @@ impl<'a> Lexer<'a> {
                             continue;
                         }
 
-                        // if operator is "-" evaluate if it is a negative number
-                        if c == '-' && self.peek().unwrap().is_digit(10) {
-                            return Token::Number(self.scan_number());
-                        }
-
                         if c == '*' && self.peek() == Some('*') {
                             self.advance();
                             self.advance();
-                            return Token::Operator("**".to_string());
+                            return self.compound_or_operator("**".to_string());
                         }
 
-                        if c == '-' && self.peek().unwrap() == '>' {
+                        if c == '-' && self.peek() == Some('>') {
                             self.advance();
                             self.advance();
                             return Token::ArrowType;
                         }
 
+                        self.advance();
+                        return self.compound_or_operator(c.to_string());
+                    }
+                    '^' => {
+                        self.advance();
+                        return self.compound_or_operator(c.to_string());
+                    }
+                    '~' => {
                         self.advance();
                         return Token::Operator(c.to_string());
                     }
@@ -455,8 +569,11 @@ impl<'a> Lexer<'a> {
                                 return Token::LogicalOperator(format!("{}{}", c, c2));
                             } else if c == '=' {
                                 return Token::Equal;
-                            } else {
+                            } else if c == '!' {
                                 return Token::Operator(c.to_string());
+                            } else {
+                                // bitwise `&` and `|`
+                                return self.compound_or_operator(c.to_string());
                             }
                         } else if c == '=' {
                             return Token::Equal;
@@ -467,6 +584,11 @@ impl<'a> Lexer<'a> {
                     '<' | '>' => {
                         self.advance();
                         if let Some(c2) = self.current_char {
+                            // shifts, `<<` and `>>`
+                            if c2 == c {
+                                self.advance();
+                                return self.compound_or_operator(format!("{}{}", c, c));
+                            }
                             if c2 == '=' {
                                 self.advance();
                                 return Token::LogicalOperator(format!("{}=", c));
@@ -478,10 +600,11 @@ impl<'a> Lexer<'a> {
                         }
                     }
                     '"' => return Token::String(self.scan_string()),
+                    '\'' => return Token::Char(self.scan_char()),
                     _ => {
-                        if c.is_digit(10) {
+                        if c.is_ascii_digit() {
                             return Token::Number(self.scan_number());
-                        } else if c.is_alphabetic() || c == '_' {
+                        } else if c.is_xid_start() || c == '_' {
                             let identifier = self.scan_identifier_keyword();
                             return match identifier.as_str() {
                                 "fn" => Token::Function,
@@ -489,12 +612,15 @@ impl<'a> Lexer<'a> {
                                 "for" => Token::For,
                                 "let" => Token::Let,
                                 "return" => Token::Return,
+                                "break" => Token::Break,
                                 "loop" => Token::Loop,
                                 "in" => Token::In,
+                                "as" => Token::As,
                                 "int" => Token::TypeInt,
                                 "string" => Token::TypeString,
                                 "float" => Token::TypeFloat,
                                 "bool" => Token::TypeBool,
+                                "char" => Token::TypeChar,
                                 "const" => Token::Const,
                                 "else" => Token::Else,
                                 "true" => Token::True,
@@ -502,7 +628,11 @@ impl<'a> Lexer<'a> {
                                 _ => Token::Identifier(identifier),
                             };
                         } else {
-                            panic!("Unexpected character: {}", c);
+                            eprintln!(
+                                "Error: Unexpected character '{}' at line: {}, col: {}",
+                                c, self.line, self.col
+                            );
+                            std::process::exit(1);
                         }
                     }
                 }
@@ -512,12 +642,23 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    // An operator directly followed by '=' is a compound assignment
+    fn compound_or_operator(&mut self, operator: String) -> Token {
+        if self.current_char == Some('=') {
+            self.advance();
+            return Token::CompoundEqual(operator);
+        }
+        Token::Operator(operator)
+    }
+
     pub fn peek_token(&mut self) -> Token {
         if let Some(token) = &self.lookahead {
             return token.clone();
         }
+        let token_line = self.token_line;
         let next_token = self.get_next_token();
         self.lookahead = Some(next_token.clone());
+        self.lookahead_line = std::mem::replace(&mut self.token_line, token_line);
         return next_token
     }
 }
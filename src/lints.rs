@@ -0,0 +1,621 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::process::exit;
+
+use crate::lexer::Token;
+use crate::sintax::{Statement, Expresion, Literal, Allow};
+use crate::table::{SymbolTable, SymbolKind, SymbolId, ScopeKind, UseType};
+
+// Lints are checks that don't stop the compilation unless they're denied. They run on
+// a program that already passed the type checker
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl Level {
+    pub fn from_name(name: &str) -> Option<Level> {
+        match name {
+            "allow" => Some(Level::Allow),
+            "warn" => Some(Level::Warn),
+            "deny" => Some(Level::Deny),
+            _ => None,
+        }
+    }
+}
+
+// Something a lint found, the line is the one `#[allow]` attributes are matched against
+pub struct Finding {
+    pub line: usize,
+    pub message: String,
+}
+
+// What the lints get to look at
+pub struct Context<'a> {
+    pub program: &'a [Statement],
+    pub table: &'a SymbolTable,
+    pub lines: &'a [usize], // Line of every expression, indexed by node id
+}
+
+pub trait Lint {
+    fn name(&self) -> &'static str;
+    fn default_level(&self) -> Level;
+    fn check(&self, context: &Context) -> Vec<Finding>;
+}
+
+// The registered lints and the level each one reports at
+pub struct Linter {
+    lints: Vec<Box<dyn Lint>>,
+    levels: HashMap<&'static str, Level>,
+    // Level of the lints that would warn, set through the `warnings` group
+    warnings: Option<Level>,
+}
+
+impl Linter {
+    pub fn new() -> Self {
+        let mut linter = Linter {
+            lints: Vec::new(),
+            levels: HashMap::new(),
+            warnings: None,
+        };
+        linter.register(Box::new(UnusedVariables));
+        linter.register(Box::new(DeadCode));
+        linter.register(Box::new(Shadowing));
+        linter.register(Box::new(BoolComparison));
+        linter.register(Box::new(InfiniteLoop));
+        linter.register(Box::new(UnreachableCode));
+        linter.register(Box::new(ConstantCondition));
+        linter.register(Box::new(EmptyBlock));
+        linter
+    }
+
+    pub fn register(&mut self, lint: Box<dyn Lint>) {
+        self.levels.insert(lint.name(), lint.default_level());
+        self.lints.push(lint);
+    }
+
+    // Returns false if there's no lint with that name, `warnings` stands for every lint that warns
+    pub fn set_level(&mut self, name: &str, level: Level) -> bool {
+        if name == "warnings" {
+            self.warnings = Some(level);
+            return true;
+        }
+        match self.levels.get_mut(name) {
+            Some(current) => {
+                *current = level;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn level(&self, lint: &dyn Lint) -> Level {
+        match self.levels[lint.name()] {
+            Level::Warn => self.warnings.unwrap_or(Level::Warn),
+            level => level,
+        }
+    }
+
+    // Sets the levels in the `[lints]` table of a ruston.toml, like `shadowing = "deny"`
+    pub fn configure(&mut self, path: &Path) {
+        let config = std::fs::read_to_string(path).ok().and_then(|source| source.parse::<toml::Table>().ok());
+        let Some(config) = config else {
+            eprintln!("Invalid config file {}", path.display());
+            exit(1);
+        };
+        let Some(lints) = config.get("lints").and_then(|lints| lints.as_table()) else {
+            return;
+        };
+        for (name, level) in lints {
+            let Some(level) = level.as_str().and_then(Level::from_name) else {
+                eprintln!("Invalid level for lint '{}' in {}, use allow, warn or deny", name, path.display());
+                exit(1);
+            };
+            if !self.set_level(name, level) {
+                eprintln!("Warning: Unknown lint '{}' in {}", name, path.display());
+            }
+        }
+    }
+
+    // Reports what the lints find, returns false if a denied lint found something
+    pub fn run(&self, context: &Context, allows: &[Allow]) -> bool {
+        for allow in allows {
+            for name in &allow.lints {
+                if name != "warnings" && !self.levels.contains_key(name.as_str()) {
+                    eprintln!("Warning: Unknown lint '{}' in the attribute at line {}", name, allow.lines.start());
+                }
+            }
+        }
+
+        let mut denied = false;
+        for lint in &self.lints {
+            let level = self.level(lint.as_ref());
+            if level == Level::Allow {
+                continue;
+            }
+            for finding in lint.check(context) {
+                let allowed = allows.iter().any(|allow| {
+                    allow.lines.contains(&finding.line) && allow.lints.iter().any(|name| name == lint.name() || name == "warnings")
+                });
+                if allowed {
+                    continue;
+                }
+                if level == Level::Deny {
+                    eprintln!("Error [{}]: {}", lint.name(), finding.message);
+                    denied = true;
+                } else {
+                    eprintln!("Warning [{}]: {}", lint.name(), finding.message);
+                }
+            }
+        }
+        !denied
+    }
+}
+
+// Calls `f` on every statement of the block, nested ones included
+fn each_statement<'a>(block: &'a [Statement], f: &mut dyn FnMut(&'a Statement)) {
+    for statement in block {
+        f(statement);
+        match statement {
+            Statement::If(_, body, else_stmt, _) => {
+                each_statement(body, f);
+                if let Some(else_stmt) = else_stmt {
+                    each_statement(std::slice::from_ref(else_stmt), f);
+                }
+            }
+            Statement::Loop(body, _) | Statement::For(_, _, body, _) | Statement::FnDeclaration(_, _, body, _) => each_statement(body, f),
+            _ => {}
+        }
+    }
+}
+
+// Calls `f` on every expression of the block, subexpressions included
+fn each_expression(block: &[Statement], f: &mut dyn FnMut(&Expresion)) {
+    each_statement(block, &mut |statement| match statement {
+        Statement::ExpressionStatement(expr) | Statement::If(expr, ..) | Statement::For(_, expr, _, _) => visit_expression(expr, f),
+        Statement::Declaration(_, Some(expr), _) | Statement::Destructure(_, expr) | Statement::Return(Some(expr)) => visit_expression(expr, f),
+        Statement::Assignment(left, right) | Statement::CompoundAssignment(left, _, right, _) => {
+            visit_expression(left, f);
+            visit_expression(right, f);
+        }
+        _ => {}
+    });
+}
+
+fn visit_expression(expr: &Expresion, f: &mut dyn FnMut(&Expresion)) {
+    f(expr);
+    match expr {
+        Expresion::Binary(left, _, right, _) | Expresion::Index(left, right, _) | Expresion::Range(left, right, _, _) => {
+            visit_expression(left, f);
+            visit_expression(right, f);
+        }
+        Expresion::FnCall(_, elements, _, _) | Expresion::Tuple(elements, _) | Expresion::Array(elements, _) => {
+            for element in elements {
+                visit_expression(element, f);
+            }
+        }
+        Expresion::Member(expr, _, _) | Expresion::TupleIndex(expr, _, _) | Expresion::Unary(_, expr, _) | Expresion::Cast(expr, _, _) => {
+            visit_expression(expr, f);
+        }
+        Expresion::Literal(..) | Expresion::Identifier(..) => {}
+    }
+}
+
+// The else branch is parsed as an `if true` with a scope of its own
+fn is_else(table: &SymbolTable, scope_id: u32) -> bool {
+    table.scope(scope_id).kind == ScopeKind::Else
+}
+
+// What a declaration the program may not use declares
+enum Declared {
+    Variable,
+    Parameter(String), // name of the function
+    Function(u32), // scope of the body
+}
+
+// The declarations the program may leave unused, with how they're used
+struct Usage {
+    declarations: Vec<(SymbolId, Declared)>,
+    reads: HashMap<SymbolId, usize>,
+    // Declarations that are given a value after they're declared
+    written: HashSet<SymbolId>,
+}
+
+impl Usage {
+    fn of(context: &Context) -> Usage {
+        let table = context.table;
+        let mut declarations = Vec::new();
+        let mut writes = HashSet::new();
+        each_statement(context.program, &mut |statement| match statement {
+            Statement::Declaration(_, _, id) => declarations.push((*id, Declared::Variable)),
+            // Assigning to a variable doesn't read it, `x += 1` included
+            Statement::Assignment(Expresion::Identifier(_, reference, _), _) | Statement::CompoundAssignment(Expresion::Identifier(_, reference, _), _, _, _) => {
+                writes.insert(*reference);
+            }
+            Statement::For(pattern, ..) | Statement::Destructure(pattern, _) => {
+                declarations.extend(pattern.bindings().into_iter().map(|(_, id)| (id, Declared::Variable)));
+            }
+            Statement::FnDeclaration(name, params, _, scope_id) => {
+                if let Some(id) = table.function(*scope_id) {
+                    declarations.push((id, Declared::Function(*scope_id)));
+                }
+                for param in params {
+                    if let Some(id) = table.declaration_in(*scope_id, param) {
+                        declarations.push((id, Declared::Parameter(name.clone())));
+                    }
+                }
+            }
+            _ => {}
+        });
+
+        let mut reads: HashMap<SymbolId, usize> = HashMap::new();
+        let mut written = HashSet::new();
+        for symbol in &table.symbols {
+            let SymbolKind::Reference { declaration: Some(declaration) } = symbol.kind else {
+                continue;
+            };
+            if writes.contains(&symbol.id) {
+                written.insert(declaration);
+                continue;
+            }
+            // A function calling itself doesn't make it used
+            let body = declarations.iter().find_map(|(id, declared)| match declared {
+                Declared::Function(body) if *id == declaration => Some(*body),
+                _ => None,
+            });
+            if body.is_some_and(|body| symbol.scope == body || table.ancestors(symbol.scope).any(|scope| scope == body)) {
+                continue;
+            }
+            *reads.entry(declaration).or_default() += 1;
+        }
+        Usage { declarations, reads, written }
+    }
+
+    // Declarations never read, `_` at the start of a name opts out
+    fn unused<'a>(&'a self, table: &'a SymbolTable) -> impl Iterator<Item = (SymbolId, &'a Declared)> + 'a {
+        self.declarations
+            .iter()
+            .filter(move |(id, _)| !self.reads.contains_key(id) && !table.symbol(*id).value.starts_with('_'))
+            .map(|(id, declared)| (*id, declared))
+    }
+}
+
+// Variables and parameters that are never read
+struct UnusedVariables;
+
+impl Lint for UnusedVariables {
+    fn name(&self) -> &'static str {
+        "unused_variables"
+    }
+
+    fn default_level(&self) -> Level {
+        Level::Warn
+    }
+
+    fn check(&self, context: &Context) -> Vec<Finding> {
+        let usage = Usage::of(context);
+        let mut findings = Vec::new();
+        for (id, declared) in usage.unused(context.table) {
+            let symbol = context.table.symbol(id);
+            let message = match declared {
+                Declared::Variable if usage.written.contains(&id) => format!("Variable '{}' declared at line {} is assigned but never read", symbol.value, symbol.occurrence),
+                Declared::Variable => format!("Unused variable '{}' at line {}", symbol.value, symbol.occurrence),
+                Declared::Parameter(function) => format!("Unused parameter '{}' of function '{}' at line {}", symbol.value, function, symbol.occurrence),
+                Declared::Function(_) => continue,
+            };
+            findings.push(Finding { line: symbol.occurrence, message });
+        }
+        findings
+    }
+}
+
+// Functions that are never called, but `main`
+struct DeadCode;
+
+impl Lint for DeadCode {
+    fn name(&self) -> &'static str {
+        "dead_code"
+    }
+
+    fn default_level(&self) -> Level {
+        Level::Warn
+    }
+
+    fn check(&self, context: &Context) -> Vec<Finding> {
+        let usage = Usage::of(context);
+        let mut findings = Vec::new();
+        for (id, declared) in usage.unused(context.table) {
+            let symbol = context.table.symbol(id);
+            if matches!(declared, Declared::Function(_)) && symbol.value != "main" {
+                let message = format!("Function '{}' declared at line {} is never called", symbol.value, symbol.occurrence);
+                findings.push(Finding { line: symbol.occurrence, message });
+            }
+        }
+        findings
+    }
+}
+
+// Variables declared with the name of another one that's still visible
+struct Shadowing;
+
+impl Lint for Shadowing {
+    fn name(&self) -> &'static str {
+        "shadowing"
+    }
+
+    fn default_level(&self) -> Level {
+        Level::Allow
+    }
+
+    fn check(&self, context: &Context) -> Vec<Finding> {
+        let table = context.table;
+        let is_variable = |id: &SymbolId| matches!(table.symbol(*id).kind, SymbolKind::Variable { .. });
+        let mut findings = Vec::new();
+        for symbol in &table.symbols {
+            if !matches!(symbol.use_type, UseType::Declaration) || !is_variable(&symbol.id) || symbol.value.starts_with('_') {
+                continue;
+            }
+            // The latest variable with the same name declared before, in this scope or an enclosing one
+            let shadowed = std::iter::once(symbol.scope)
+                .chain(table.ancestors(symbol.scope))
+                .flat_map(|scope_id| table.scope(scope_id).declarations.iter())
+                .filter(|&&id| id < symbol.id && is_variable(&id) && table.symbol(id).value == symbol.value)
+                .max();
+            if let Some(&shadowed) = shadowed {
+                let message = format!("Variable '{}' at line {} shadows the one declared at line {}", symbol.value, symbol.occurrence, table.symbol(shadowed).occurrence);
+                findings.push(Finding { line: symbol.occurrence, message });
+            }
+        }
+        findings
+    }
+}
+
+// `x == true` and the like, the bool itself or its negation reads better
+struct BoolComparison;
+
+impl Lint for BoolComparison {
+    fn name(&self) -> &'static str {
+        "bool_comparison"
+    }
+
+    fn default_level(&self) -> Level {
+        Level::Warn
+    }
+
+    fn check(&self, context: &Context) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        each_expression(context.program, &mut |expr| {
+            let Expresion::Binary(left, Token::LogicalOperator(operator), right, id) = expr else {
+                return;
+            };
+            let is_bool = |expr: &Expresion| matches!(expr, Expresion::Literal(Literal::Boolean(_), _));
+            if (operator == "==" || operator == "!=") && (is_bool(left) || is_bool(right)) {
+                let line = context.lines[*id as usize];
+                let message = format!("Comparison with a bool literal at line {}, use the value or its negation", line);
+                findings.push(Finding { line, message });
+            }
+        });
+        findings
+    }
+}
+
+// `loop` without a way out
+struct InfiniteLoop;
+
+impl Lint for InfiniteLoop {
+    fn name(&self) -> &'static str {
+        "infinite_loop"
+    }
+
+    fn default_level(&self) -> Level {
+        Level::Warn
+    }
+
+    fn check(&self, context: &Context) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        each_statement(context.program, &mut |statement| {
+            let Statement::Loop(body, scope_id) = statement else {
+                return;
+            };
+            if !exits(body, true) {
+                let line = context.table.scope(*scope_id).line;
+                findings.push(Finding { line, message: format!("The loop at line {} never exits, it has no reachable return or break", line) });
+            }
+        });
+        findings
+    }
+}
+
+// Code after the statements that diverge is never reached
+struct UnreachableCode;
+
+impl Lint for UnreachableCode {
+    fn name(&self) -> &'static str {
+        "unreachable_code"
+    }
+
+    fn default_level(&self) -> Level {
+        Level::Warn
+    }
+
+    fn check(&self, context: &Context) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        each_block(context.program, &mut |block| {
+            let Some(position) = block.iter().position(diverges) else {
+                return;
+            };
+            let rest = &block[position + 1..];
+            if rest.is_empty() {
+                return;
+            }
+            let after = match &block[position] {
+                Statement::Return(_) => "a return",
+                Statement::Break => "a break",
+                Statement::Loop(..) => "a loop that never exits",
+                _ => "an if that doesn't go on",
+            };
+            // `return;` and `break;` have no line of their own
+            let line = rest.iter().chain(&block[position..=position]).find_map(|statement| statement_line(statement, context)).unwrap_or_default();
+            findings.push(Finding { line, message: format!("Unreachable code at line {}, after {}", line, after) });
+        });
+        findings
+    }
+}
+
+// Calls `f` on the block and every block nested in it
+fn each_block<'a>(block: &'a [Statement], f: &mut dyn FnMut(&'a [Statement])) {
+    f(block);
+    for statement in block {
+        match statement {
+            Statement::If(_, body, else_stmt, _) => {
+                each_block(body, f);
+                if let Some(else_stmt) = else_stmt {
+                    each_block(std::slice::from_ref(else_stmt), f);
+                }
+            }
+            Statement::Loop(body, _) | Statement::For(_, _, body, _) | Statement::FnDeclaration(_, _, body, _) => each_block(body, f),
+            _ => {}
+        }
+    }
+}
+
+fn statement_line(statement: &Statement, context: &Context) -> Option<usize> {
+    match statement {
+        Statement::ExpressionStatement(expr) | Statement::Assignment(expr, _) | Statement::CompoundAssignment(expr, _, _, _) | Statement::Destructure(_, expr) | Statement::Return(Some(expr)) => {
+            Some(context.lines[expr.id() as usize])
+        }
+        Statement::Declaration(_, _, id) => Some(context.table.symbol(*id).occurrence),
+        Statement::If(_, _, _, scope_id) | Statement::Loop(_, scope_id) | Statement::For(_, _, _, scope_id) | Statement::FnDeclaration(_, _, _, scope_id) => {
+            Some(context.table.scope(*scope_id).line)
+        }
+        Statement::Return(None) | Statement::Break => None,
+    }
+}
+
+// The value of a condition that's a bool literal
+fn constant(cond: &Expresion) -> Option<bool> {
+    match cond {
+        Expresion::Literal(Literal::Boolean(value), _) => Some(*value),
+        _ => None,
+    }
+}
+
+// Whether a reachable statement of the block leaves the loop it's in. A return in a function
+// declared in it doesn't, a break only does if `breaks`, it may belong to a nested loop
+fn exits(block: &[Statement], breaks: bool) -> bool {
+    for statement in block {
+        let leaves = match statement {
+            Statement::Return(_) => true,
+            Statement::Break => breaks,
+            Statement::If(cond, body, else_stmt, _) => {
+                (constant(cond) != Some(false) && exits(body, breaks))
+                    || (constant(cond) != Some(true) && else_stmt.as_ref().is_some_and(|else_stmt| exits(std::slice::from_ref(else_stmt), breaks)))
+            }
+            Statement::Loop(body, _) | Statement::For(_, _, body, _) => exits(body, false),
+            _ => false,
+        };
+        if leaves {
+            return true;
+        }
+        if diverges(statement) {
+            return false;
+        }
+    }
+    false
+}
+
+// Whether the statements after this one are never reached
+fn diverges(statement: &Statement) -> bool {
+    let block_diverges = |block: &[Statement]| block.iter().any(diverges);
+    match statement {
+        Statement::Return(_) | Statement::Break => true,
+        Statement::Loop(body, _) => !exits(body, true),
+        Statement::If(cond, body, else_stmt, _) => {
+            let else_diverges = else_stmt.as_ref().is_some_and(|else_stmt| diverges(else_stmt));
+            match constant(cond) {
+                Some(true) => block_diverges(body),
+                Some(false) => else_diverges,
+                None => block_diverges(body) && else_diverges,
+            }
+        }
+        _ => false,
+    }
+}
+
+// Conditions made of literals only, the if always takes the same branch
+struct ConstantCondition;
+
+impl Lint for ConstantCondition {
+    fn name(&self) -> &'static str {
+        "constant_condition"
+    }
+
+    fn default_level(&self) -> Level {
+        Level::Warn
+    }
+
+    fn check(&self, context: &Context) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        each_statement(context.program, &mut |statement| {
+            let Statement::If(cond, _, else_stmt, scope_id) = statement else {
+                return;
+            };
+            if is_else(context.table, *scope_id) || !is_constant(cond) {
+                return;
+            }
+            let line = context.lines[cond.id() as usize];
+            let message = match (constant(cond), else_stmt) {
+                (Some(false), _) => format!("The condition at line {} is always false, its block never runs", line),
+                (Some(true), Some(_)) => format!("The condition at line {} is always true, the else branch never runs", line),
+                (Some(true), None) => format!("The condition at line {} is always true", line),
+                (None, _) => format!("The condition at line {} is constant", line),
+            };
+            findings.push(Finding { line, message });
+        });
+        findings
+    }
+}
+
+fn is_constant(expr: &Expresion) -> bool {
+    match expr {
+        Expresion::Literal(..) => true,
+        Expresion::Binary(left, _, right, _) => is_constant(left) && is_constant(right),
+        Expresion::Unary(_, expr, _) | Expresion::Cast(expr, _, _) => is_constant(expr),
+        _ => false,
+    }
+}
+
+// Blocks with nothing in them
+struct EmptyBlock;
+
+impl Lint for EmptyBlock {
+    fn name(&self) -> &'static str {
+        "empty_block"
+    }
+
+    fn default_level(&self) -> Level {
+        Level::Warn
+    }
+
+    fn check(&self, context: &Context) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        each_statement(context.program, &mut |statement| {
+            let (body, scope_id, what) = match statement {
+                Statement::If(_, body, _, scope_id) if is_else(context.table, *scope_id) => (body, scope_id, "else"),
+                Statement::If(_, body, _, scope_id) => (body, scope_id, "if"),
+                Statement::Loop(body, scope_id) => (body, scope_id, "loop"),
+                Statement::For(_, _, body, scope_id) => (body, scope_id, "for"),
+                Statement::FnDeclaration(_, _, body, scope_id) => (body, scope_id, "function"),
+                _ => return,
+            };
+            if body.is_empty() {
+                let line = context.table.scope(*scope_id).line;
+                findings.push(Finding { line, message: format!("Empty {} block at line {}", what, line) });
+            }
+        });
+        findings
+    }
+}
@@ -1,4 +1,5 @@
 // let me get the output without warnings geez
+#![allow(clippy::needless_return, clippy::upper_case_acronyms, clippy::enum_variant_names)]
 
 mod lexer;
 mod sintax;
@@ -6,6 +7,8 @@ mod table;
 mod tree_display;
 mod visitor;
 mod generator;
+mod resolver;
+mod lints;
 pub mod semantic;
 
 use lexer::{Lexer,Token};
@@ -14,17 +17,23 @@ use sintax::Sintax;
 use tree_display::display_tree;
 use table::{Symbol,SymbolTable, UseType};
 use semantic::Semantic;
+use resolver::Resolver;
+use sintax::{Statement, Allow};
 
-use generator:: PythonGenerator;
+use generator::{PythonGenerator, Overflow};
+use lints::{Linter, Level, Context};
+use std::collections::HashMap;
 
 
 fn main() {
     // get the path to the file
-    let args  =  std::env::args().collect::<Vec<String>>();
+    let mut args  =  std::env::args().collect::<Vec<String>>();
+    let overflow = overflow_flag(&mut args);
+    let lint_levels = lint_flags(&mut args);
     if  args.len() < 2
     {
         // print the usage
-        println!("Usage: cargo run <path_to_file>");
+        println!("Usage: cargo run <path_to_file> [--overflow=panic|wrap] [-A|-W|-D <lint>]...");
         return;
     }
     let path = &args[1];
@@ -37,13 +46,23 @@ fn main() {
     let source = std::fs::read_to_string(path).expect("Could not read file");
     // create a lexer
     let mut lexer = Lexer::new(&source);
+    let linter = configure_lints(path, lint_levels);
 
     if args.len() == 2{
         let mut parser = Sintax::new(lexer.clone());
         parser.parse();
-        // display_tree(&parser.program);
+        // display_tree(&parser.program, &HashMap::new());
         // println!("{:#?}", parser.table);
-        let mut generator = PythonGenerator::new(parser.program, parser.table);
+        let table = resolve_names(&parser.program, parser.table);
+        // the generated code depends on the types of the expressions
+        let mut semantic = Semantic::new(parser.program.clone(), table);
+        if !semantic.semantic_check() {
+            std::process::exit(1);
+        }
+        if !run_lints(&linter, &parser.program, &semantic.table, &parser.lines, &parser.allows) {
+            std::process::exit(1);
+        }
+        let mut generator = PythonGenerator::new(parser.program, semantic.table, semantic.types, semantic.promotions, overflow);
         let code = generator.generate();
         println!("{}", code);
         return
@@ -75,16 +94,10 @@ fn main() {
         1 => {
             let mut parser = Sintax::new(lexer);
             parser.parse();
-            display_tree(&parser.program);
-            let table = parser.table;
-
-            println!("{:<20} | {:<20} | {:<20}", "Name", "Type", "Data Type");
-            println!("{:-<62}", "");
-            for symbol in table.all_scopes {
-                for symbol in symbol.1 {
-                    println!("{:<20} | {:<20} | {:<20}", symbol.0, format!("{:?}", symbol.1.use_type), symbol.1.kind);
-                }
-            }
+            display_tree(&parser.program, &HashMap::new());
+            let table = resolve_names(&parser.program, parser.table);
+
+            print_symbols(&table);
             
 
 
@@ -92,18 +105,17 @@ fn main() {
         2 => {
             let mut parser = Sintax::new(lexer);
             parser.parse();
-            let mut semantic = Semantic::new(parser.program, parser.table);
-            semantic.semantic_check();
-
-            let table = semantic.table;
-
-            println!("{:<20} | {:<20} | {:<20}", "Name", "Type", "Data Type");
-            println!("{:-<62}", "");
-            for symbol in table.all_scopes {
-                for symbol in symbol.1 {
-                    println!("{:<20} | {:<20} | {:<20}", symbol.0, format!("{:?}", symbol.1.use_type), symbol.1.kind);
-                }
+            let table = resolve_names(&parser.program, parser.table);
+            let mut semantic = Semantic::new(parser.program.clone(), table);
+            let passed = semantic.semantic_check();
+            // the types found before an error are shown as well
+            display_tree(&parser.program, &semantic.types);
+            if passed {
+                println!("Success: Type checking passed");
+                run_lints(&linter, &parser.program, &semantic.table, &parser.lines, &parser.allows);
             }
+
+            print_symbols(&semantic.table);
             
         }
         _ => {
@@ -115,6 +127,109 @@ fn main() {
 }
 
 
+// Takes the `--overflow=panic|wrap` flag out of the arguments, fixed width
+// arithmetic panics on overflow unless asked to wrap
+fn overflow_flag(args: &mut Vec<String>) -> Overflow {
+    let mut overflow = Overflow::Panic;
+    let mut i = 0;
+    while i < args.len() {
+        if let Some(mode) = args[i].strip_prefix("--overflow=") {
+            overflow = match mode {
+                "panic" => Overflow::Panic,
+                "wrap" => Overflow::Wrap,
+                _ => {
+                    eprintln!("Invalid overflow mode '{}', use panic or wrap", mode);
+                    std::process::exit(1);
+                }
+            };
+            args.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+    overflow
+}
+
+// Binds every use of a name to its declaration, stopping if any can't be found
+fn resolve_names(program: &[Statement], table: SymbolTable) -> SymbolTable {
+    let mut resolver = Resolver::new(table);
+    if !resolver.resolve(program) {
+        std::process::exit(1);
+    }
+    resolver.table
+}
+
+// Takes the `-A`, `-W` and `-D` flags out of the arguments, they allow, warn or deny the
+// lint that follows them, `-D warnings` denies every lint that warns
+fn lint_flags(args: &mut Vec<String>) -> Vec<(String, Level)> {
+    let mut levels = Vec::new();
+    let mut i = 0;
+    while i < args.len() {
+        let level = match args[i].as_str() {
+            "-A" => Level::Allow,
+            "-W" => Level::Warn,
+            "-D" => Level::Deny,
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        if i + 1 >= args.len() {
+            eprintln!("Missing lint name after {}", args[i]);
+            std::process::exit(1);
+        }
+        levels.push((args[i + 1].clone(), level));
+        args.drain(i..i + 2);
+    }
+    levels
+}
+
+// The lint levels come from the ruston.toml closest to the file, then from the command line
+fn configure_lints(path: &str, levels: Vec<(String, Level)>) -> Linter {
+    let mut linter = Linter::new();
+    // a relative path would stop the search at the working directory
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| std::path::PathBuf::from(path));
+    let config = path
+        .ancestors()
+        .skip(1)
+        .map(|dir| dir.join("ruston.toml"))
+        .find(|config| config.is_file());
+    if let Some(config) = config {
+        linter.configure(&config);
+    }
+    for (name, level) in levels {
+        if !linter.set_level(&name, level) {
+            eprintln!("Warning: Unknown lint '{}'", name);
+        }
+    }
+    linter
+}
+
+// Returns false if a denied lint found something
+fn run_lints(linter: &Linter, program: &[Statement], table: &SymbolTable, lines: &[usize], allows: &[Allow]) -> bool {
+    let context = Context { program, table, lines };
+    linter.run(&context, allows)
+}
+
+fn print_symbols(table: &SymbolTable) {
+    println!("{:<5} | {:<20} | {:<20} | {:<20} | {:<20}", "Id", "Scope", "Name", "Type", "Data Type");
+    println!("{:-<93}", "");
+    print_scope(table, 0);
+}
+
+// Prints the symbols of a scope in source order, followed by its nested scopes
+fn print_scope(table: &SymbolTable, scope_id: u32) {
+    let scope = table.scope(scope_id);
+    let scope_name = format!("{} ({:?})", scope.id, scope.kind);
+    for symbol in table.symbols.iter().filter(|symbol| symbol.scope == scope_id) {
+        println!("{:<5} | {:<20} | {:<20} | {:<20} | {:<20}", symbol.id, scope_name, symbol.value, format!("{:?}", symbol.use_type), symbol.kind);
+    }
+    for &child in table.children(scope_id) {
+        print_scope(table, child);
+    }
+}
+
+
 fn get_type_and_value(token: &Token) -> (&str, String) {
     match token {
         Token::ArrowType => ("ArrowType", "->".to_string()),
@@ -131,12 +246,14 @@ fn get_type_and_value(token: &Token) -> (&str, String) {
         Token::Comma => ("Comma", ",".to_string()),
         Token::Semicolon => ("Semicolon", ";".to_string()),
         Token::Dot => ("Dot", ".".to_string()),
+        Token::Hash => ("Hash", "#".to_string()),
         Token::Equal => ("Equal", "=".to_string()),
+        Token::CompoundEqual(value) => ("CompoundEqual", format!("{}=", value)),
         Token::Identifier(value) => ("Identifier", value.clone()),
         Token::Number(value) => {
             match value {
                 lexer::Number::Integer(value) => ("Number:Integer", value.to_string()),
-                lexer::Number::Float(value) => ("Number:Float", value.to_string()),
+                lexer::Number::Float(_, text) => ("Number:Float", text.clone()),
             }
         }
         Token::Operator(value) => ("Operator", value.clone()),
@@ -147,13 +264,17 @@ fn get_type_and_value(token: &Token) -> (&str, String) {
         Token::Let => ("Let", "let".to_string()),
         Token::Loop => ("Loop", "loop".to_string()),
         Token::Return => ("Return", "return".to_string()),
+        Token::Break => ("Break", "break".to_string()),
+        Token::As => ("As", "as".to_string()),
         Token::String(value) => ("String", value.clone()),
+        Token::Char(value) => ("Char", format!("{:?}", value)),
         Token::EOF => ("EOF", "".to_string()),
         Token::Colon => ("Colon", ":".to_string()),
         Token::TypeInt => ("TypeInt", "int".to_string()),
         Token::TypeFloat => ("TypeFloat", "float".to_string()),
         Token::TypeString => ("TypeString", "string".to_string()),
         Token::TypeBool => ("TypeBool", "bool".to_string()),
+        Token::TypeChar => ("TypeChar", "char".to_string()),
         Token::Const => ("Const", "const".to_string()),
         Token::Range => ("Range", "..".to_string()),
         Token::RangeInclusive => ("RangeInclusive", "..=".to_string()),
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use crate::sintax::{Statement, Expresion, Pattern};
+use crate::table::{SymbolTable, SymbolId};
+
+// The resolver walks the program with a stack of scopes and binds every
+// reference recorded by the parser to the declaration it refers to, so later
+// passes never have to look a symbol up by its name alone.
+pub struct Resolver {
+    pub table: SymbolTable,
+    // Names visible in each active scope, innermost last
+    scopes: Vec<HashMap<String, SymbolId>>,
+    error: bool,
+}
+
+impl Resolver {
+    pub fn new(table: SymbolTable) -> Self {
+        Resolver {
+            table,
+            scopes: Vec::new(),
+            error: false,
+        }
+    }
+
+    // Returns false if a name could not be resolved
+    pub fn resolve(&mut self, program: &[Statement]) -> bool {
+        self.scopes.push(HashMap::new());
+        self.resolve_block(program);
+        self.scopes.pop();
+        !self.error
+    }
+
+    fn resolve_block(&mut self, block: &[Statement]) {
+        // Functions can be called from anywhere in the block they are declared in
+        for statement in block {
+            if let Statement::FnDeclaration(name, _, _, fn_scope) = statement {
+                if let Some(id) = self.table.function(*fn_scope) {
+                    self.declare(name, id);
+                }
+            }
+        }
+        for statement in block {
+            self.resolve_statement(statement);
+        }
+    }
+
+    fn resolve_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::ExpressionStatement(expr) => self.resolve_expression(expr),
+            Statement::Declaration(name, expr, id) => {
+                // The initializer is resolved first, so `let x = x + 1;` uses the outer `x`
+                if let Some(expr) = expr {
+                    self.resolve_expression(expr);
+                }
+                self.declare(name, *id);
+            }
+            Statement::Destructure(pattern, expr) => {
+                self.resolve_expression(expr);
+                self.scopes.push(HashMap::new());
+                self.declare_pattern(pattern);
+                let names = self.scopes.pop().unwrap();
+                self.scopes.last_mut().unwrap().extend(names);
+            }
+            Statement::Assignment(left, right) | Statement::CompoundAssignment(left, _, right, _) => {
+                self.resolve_expression(left);
+                self.resolve_expression(right);
+            }
+            Statement::If(cond, body, else_stmt, _) => {
+                self.resolve_expression(cond);
+                self.scopes.push(HashMap::new());
+                self.resolve_block(body);
+                self.scopes.pop();
+                if let Some(else_stmt) = else_stmt {
+                    self.resolve_statement(else_stmt);
+                }
+            }
+            Statement::Loop(body, _) => {
+                self.scopes.push(HashMap::new());
+                self.resolve_block(body);
+                self.scopes.pop();
+            }
+            Statement::For(pattern, range, body, _) => {
+                self.resolve_expression(range);
+                self.scopes.push(HashMap::new());
+                self.declare_pattern(pattern);
+                self.resolve_block(body);
+                self.scopes.pop();
+            }
+            Statement::FnDeclaration(name, params, body, fn_scope) => {
+                self.scopes.push(HashMap::new());
+                for param in params {
+                    if self.scopes.last().unwrap().contains_key(param) {
+                        eprintln!("Name Error: Parameter '{}' is declared more than once in function '{}'", param, name);
+                        self.error = true;
+                    }
+                    if let Some(id) = self.table.declaration_in(*fn_scope, param) {
+                        self.declare(param, id);
+                    }
+                }
+                self.resolve_block(body);
+                self.scopes.pop();
+            }
+            Statement::Return(expr) => {
+                if let Some(expr) = expr {
+                    self.resolve_expression(expr);
+                }
+            }
+            Statement::Break => {}
+        }
+    }
+
+    fn resolve_expression(&mut self, expr: &Expresion) {
+        match expr {
+            Expresion::Literal(..) => {}
+            Expresion::Identifier(name, reference, _) => {
+                if let Some(declaration) = self.find(name) {
+                    self.table.bind(*reference, declaration);
+                } else {
+                    let line = self.table.symbol(*reference).occurrence;
+                    eprintln!("Name Error: Cannot find value '{}' in this scope at line {}", name, line);
+                    self.error = true;
+                }
+            }
+            Expresion::FnCall(name, args, reference, _) => {
+                // Calls to functions that aren't declared in the program are left unbound,
+                // they're expected to be provided by the target language
+                if let Some(declaration) = self.find(name) {
+                    self.table.bind(*reference, declaration);
+                }
+                for arg in args {
+                    self.resolve_expression(arg);
+                }
+            }
+            Expresion::Binary(left, _, right, _) => {
+                self.resolve_expression(left);
+                self.resolve_expression(right);
+            }
+            Expresion::Tuple(elements, _) | Expresion::Array(elements, _) => {
+                for element in elements {
+                    self.resolve_expression(element);
+                }
+            }
+            Expresion::Index(array, index, _) => {
+                self.resolve_expression(array);
+                self.resolve_expression(index);
+            }
+            Expresion::Member(expr, _, _) | Expresion::TupleIndex(expr, _, _) | Expresion::Unary(_, expr, _) | Expresion::Cast(expr, _, _) => {
+                self.resolve_expression(expr);
+            }
+            Expresion::Range(start, end, _, _) => {
+                self.resolve_expression(start);
+                self.resolve_expression(end);
+            }
+        }
+    }
+
+    // the scope on top of the stack must be a new one, so repeated names can be found
+    fn declare_pattern(&mut self, pattern: &Pattern) {
+        for (name, id) in pattern.bindings() {
+            if self.scopes.last().unwrap().contains_key(name) {
+                let line = self.table.symbol(id).occurrence;
+                eprintln!("Name Error: '{}' is bound more than once in the pattern {} at line {}", name, pattern, line);
+                self.error = true;
+            }
+            self.declare(name, id);
+        }
+    }
+
+    fn declare(&mut self, name: &str, id: SymbolId) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), id);
+        }
+    }
+
+    fn find(&self, name: &str) -> Option<SymbolId> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name).copied())
+    }
+}
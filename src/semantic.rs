@@ -1,584 +1,659 @@
-use crate::sintax::{Statement, DataType, Expresion, Literal};
+use std::collections::{HashMap, HashSet};
+
+use crate::sintax::{Statement, DataType, Expresion, Literal, NodeId, IntType, Pattern};
 use crate::lexer::{Token, Number};
-use crate::table::{SymbolTable, SymbolKind};
+use crate::table::{SymbolTable, SymbolKind, SymbolId};
 use crate::tree_display::print_expression;
 
 pub struct Semantic {
     program: Vec<Statement>,
     pub table: SymbolTable,
+    // Type of every expression that checked out, keyed by its node id. Integer literals
+    // have the type the context gives them, `int` if nothing does
+    pub types: HashMap<NodeId, DataType>,
+    // Binary expressions with an integer operand converted to float, see `promotion`
+    pub promotions: HashMap<NodeId, Promotion>,
+    // Line and value each variable without an annotation got its type from
+    inferred: HashMap<SymbolId, (usize, Expresion)>,
+    // Variables with a value at the statement being checked
+    flow: Flow,
+    // Flows at the breaks of each loop the statement is in, innermost last
+    breaks: Vec<Vec<Flow>>,
+}
+
+// What's known about the variables on every path to a point of the program
+#[derive(Clone, Default)]
+struct Flow {
+    // Declarations assigned on every path
+    assigned: HashSet<SymbolId>,
+    // Declarations assigned on some paths only, with the branches of a path that leaves them unset
+    unset_paths: HashMap<SymbolId, Vec<Branch>>,
+    // Whether every path returned or broke out of a loop already, nothing after is reached
+    unreachable: bool,
+}
+
+// A branch of the program that can be skipped
+#[derive(Clone)]
+enum Branch {
+    // The condition of an if is true or false
+    Condition(Expresion, bool),
+    // A for loop over the expression doesn't run
+    NoIterations(Expresion),
+    // A break ends the loop at the line
+    Break(usize),
+}
+
+impl Flow {
+    fn assign(&mut self, declaration: SymbolId) {
+        self.assigned.insert(declaration);
+        self.unset_paths.remove(&declaration);
+    }
+
+    // Joins the flows of the paths from `before` to the same point, tagged with the branch they
+    // take. A variable is assigned if every path assigns it, otherwise a path that doesn't is kept
+    fn join(before: &Flow, paths: Vec<(Flow, Option<Branch>)>) -> Flow {
+        let paths: Vec<_> = paths.into_iter().filter(|(flow, _)| !flow.unreachable).collect();
+        let Some(((first, _), others)) = paths.split_first() else {
+            // No path gets there
+            return Flow { unreachable: true, ..before.clone() };
+        };
+        let assigned: HashSet<SymbolId> = first.assigned.iter()
+            .filter(|declaration| others.iter().all(|(flow, _)| flow.assigned.contains(declaration)))
+            .copied()
+            .collect();
+        let partial: HashSet<SymbolId> = paths.iter()
+            .flat_map(|(flow, _)| flow.assigned.iter().chain(flow.unset_paths.keys()))
+            .filter(|declaration| !assigned.contains(declaration))
+            .copied()
+            .collect();
+
+        let mut unset_paths = HashMap::new();
+        for declaration in partial {
+            let inherited = before.unset_paths.get(&declaration).cloned().unwrap_or_default();
+            let unset: Vec<_> = paths.iter().filter(|(flow, _)| !flow.assigned.contains(&declaration)).collect();
+            // Paths in a branch start with the path from before it
+            let path = if unset.len() == paths.len() && !inherited.is_empty() {
+                inherited
+            } else {
+                let Some((flow, branch)) = unset.first() else {
+                    continue;
+                };
+                let rest = flow.unset_paths.get(&declaration).and_then(|path| path.get(inherited.len()..)).unwrap_or_default();
+                let mut path = inherited;
+                path.extend(branch.clone());
+                path.extend_from_slice(rest);
+                path
+            };
+            unset_paths.insert(declaration, path);
+        }
+        Flow { assigned, unset_paths, unreachable: false }
+    }
+}
+
+// Side of a binary expression that was implicitly converted
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Promotion {
+    Left,
+    Right,
 }
 
 impl Semantic {
     pub fn new(program: Vec<Statement>, table: SymbolTable) -> Self {
         Semantic {
             program,
-            table
+            table,
+            types: HashMap::new(),
+            promotions: HashMap::new(),
+            inferred: HashMap::new(),
+            flow: Flow::default(),
+            breaks: Vec::new(),
         }
     }
 
-    pub fn semantic_check(&mut self) {
-        let mut error = false;
-        // Iterate over all statements in the program
+    // Returns true if no type errors were found
+    pub fn semantic_check(&mut self) -> bool {
+        // Check until a type error is found, it has been reported by then
         for statement in &self.program.clone() {
-            // Check until a type error is found
             if !self.check_type(statement) {
-                error = true;
-                break
+                return false;
             }
         }
-        // If no type errors were found, print success message
-        // TODO: This prints success even if there's a failure in the type checking, probably a recursion thing
-        if !error {
-            println!("Success: Type checking passed");
-        }
+        true
     }
 
     fn check_type(&mut self, statement: &Statement) -> bool {
-        // Checking the types involves either checking innermost statements
-        // or collecting the types of the contained expressions, to then 
-        // check wether they match or not
-        let mut type_collection: Vec<DataType> = Vec::new();
-        let mut valid = false;
-        
+        // Checking a statement involves either checking its innermost statements
+        // or getting the types of its expressions, to then check whether they match or not
         match statement {
             // Check the type of the innermost statements
-            Statement::FnDeclaration(_, _, body, _) => {
-                for statement in body {
-                    // Check until a type error is found
-                    valid = self.check_type(statement);
-                    if !valid {
-                        break;
+            Statement::FnDeclaration(_, params, body, scope_id) => {
+                // The body sees the variables assigned before the function, and its parameters
+                let outer = self.flow.clone();
+                let loops = std::mem::take(&mut self.breaks);
+                for param in params {
+                    if let Some(param_id) = self.table.declaration_in(*scope_id, param) {
+                        self.flow.assign(param_id);
                     }
                 }
+                let valid = self.check_block(body);
+                self.flow = outer;
+                self.breaks = loops;
                 return valid;
             }
             Statement::If(cond, body, else_stmt,_) => {
-                // Collect the types of the condition
-                type_collection = self.collect_types(cond, type_collection);
-                
-                if let DataType::Boolean = type_collection[0] {
-                    // Validate statements if the condition results in a boolean
-                    for statement in body {
-                        // Check until a type error is found
-                        valid = self.check_type(statement);
-                    }
-                    if let Some(else_stmt) = else_stmt {
-                        valid =  self.check_type(else_stmt);
-                    }
-                } else {
-                    println!("Type Error: Condition must result in a boolean");
+                let Some(cond_type) = self.check_expression(cond) else {
+                    return false;
+                };
+                if cond_type != DataType::Boolean {
+                    eprintln!("Type Error: Condition must result in a boolean, found {}", cond_type);
                     print_expression(cond);
-                    println!();
+                    eprintln!();
+                    return false;
                 }
-                return valid;
-            }
-            Statement::Loop(body,_) => {
-                for statement in body {
-                    // Check until a type error is found
-                    valid = self.check_type(statement);
-                    if !valid {
-                        break;
+                // Validate statements if the condition results in a boolean
+                let before = self.flow.clone();
+                if !self.check_block(body) {
+                    return false;
+                }
+                let then_flow = std::mem::replace(&mut self.flow, before.clone());
+                if let Some(else_stmt) = else_stmt {
+                    if !self.check_type(else_stmt) {
+                        return false;
                     }
                 }
-                return valid;
+                let else_flow = std::mem::take(&mut self.flow);
+                // A constant condition always takes the same branch, an `else` is an `if true`
+                self.flow = match cond {
+                    Expresion::Literal(Literal::Boolean(true), _) => then_flow,
+                    Expresion::Literal(Literal::Boolean(false), _) => else_flow,
+                    _ => Flow::join(&before, vec![
+                        (then_flow, Some(Branch::Condition(cond.clone(), true))),
+                        (else_flow, Some(Branch::Condition(cond.clone(), false))),
+                    ]),
+                };
+                return true;
             }
-            Statement::For(_, range, body, _) => {
-                // The range is given by either a range expression or an array, both of which
-                // can be validated by just collecting their types, and checking if they only include
-                // integers.
-                let mut valid_range = false;
-                let types: Vec<DataType> = self.collect_types(range, type_collection);
-
-                match range {
-                    Expresion::Range(_, _, _) => {
-                        if self.check_collection(types) {
-                            valid_range = true;
-                        }
-                    }
-                    Expresion::Array(_) => {
-                        if types[0] == DataType::Integer {
-                            valid_range = true;
-                        }
-                    }
-                    _ => {}
-                }
-                
-                // Evaluate statements only if range was valid
-                if valid_range {
-                    for statement in body {
-                        // Check until a type error is found
-                        valid = self.check_type(statement);
-                        if !valid {
-                            break;
-                        }
-                    }
-                } else {
-                    println!("Type Error: Invalid range, use only integers");
-                    println!("{:?}", print_expression(range));
+            Statement::Loop(body, scope_id) => {
+                let before = self.flow.clone();
+                self.breaks.push(Vec::new());
+                let valid = self.check_block(body);
+                let breaks = self.breaks.pop().unwrap_or_default();
+                if !valid {
+                    return false;
                 }
-
-                return valid;
+                // The loop only ends at a break, if it has any
+                let line = self.table.scope(*scope_id).line;
+                self.flow = Flow::join(&before, breaks.into_iter().map(|flow| (flow, Some(Branch::Break(line)))).collect());
+                return true;
+            }
+            Statement::For(pattern, range, body, _) => {
+                let Some(var_type) = self.iteration_type(range) else {
+                    return false;
+                };
+                let before = self.flow.clone();
+                if !self.bind_pattern(pattern, &var_type) {
+                    eprint!("for ({} in ", pattern);
+                    print_expression(range);
+                    eprintln!(")");
+                    return false;
+                }
+                self.breaks.push(Vec::new());
+                let valid = self.check_block(body);
+                let breaks = self.breaks.pop().unwrap_or_default();
+                if !valid {
+                    return false;
+                }
+                // The body may not run at all, or end at a break
+                let body_flow = std::mem::take(&mut self.flow);
+                let mut paths = vec![(before.clone(), Some(Branch::NoIterations(range.clone()))), (body_flow, None)];
+                paths.extend(breaks.into_iter().map(|flow| (flow, None)));
+                self.flow = Flow::join(&before, paths);
+                return true;
             }
-            
-
-            // Collect the types of the contained expressions
             Statement::Return(expr) => {
                 if let Some(expr) = expr {
-                    type_collection = self.collect_types(expr, type_collection);
-                    if !self.check_collection(type_collection.clone()) {
-                        println!("Type Error: Mismatching types in statement");
-                        print_expression(expr);
-                        println!();
+                    if self.check_expression(expr).is_none() {
                         return false;
                     }
-                    return true;
-                } else {
-                    return true;
                 }
+                self.flow.unreachable = true;
+                return true;
             }
-            Statement::Declaration(id,  expr) => {
-                // Check type for inference
-                let id_type = self.collect_id_type(id);
-
-                if id_type != DataType::Void {
-                    type_collection.push(id_type.clone());
+            Statement::Break => {
+                let Some(breaks) = self.breaks.last_mut() else {
+                    eprintln!("Type Error: break outside of a loop");
+                    return false;
+                };
+                breaks.push(self.flow.clone());
+                self.flow.unreachable = true;
+                return true;
+            }
+            Statement::Destructure(pattern, expr) => {
+                let Some(value_type) = self.check_expression(expr) else {
+                    return false;
+                };
+                if !self.bind_pattern(pattern, &value_type) {
+                    eprint!("let {} = ", pattern);
+                    print_expression(expr);
+                    eprintln!();
+                    return false;
                 }
-                
-                if let Some(expr) = expr {
-                    type_collection = self.collect_types(expr, type_collection);
+                return true;
+            }
+            Statement::Declaration(id,  expr, symbol) => {
+                // Void if the declaration has no type, it's inferred from the value then
+                let id_type = self.collect_id_type(*symbol);
 
-                    // Infer type if it's not defined
-                    if id_type == DataType::Void && type_collection.len() == 1{
-                        self.table.update_var_type(id, type_collection[0].clone());
+                if let Some(expr) = expr {
+                    if !self.check_literal_range(&id_type, expr) {
+                        return false;
                     }
+                    let Some(value_type) = self.check_expression(expr) else {
+                        return false;
+                    };
 
-                    if !self.check_collection(type_collection.clone()) {
-                        println!("Type Error: Mismatching types in declaration");
-                        print!("let {} = ", id);
+                    if id_type == DataType::Void {
+                        self.infer(*symbol, &value_type, *symbol, expr);
+                    } else if unify(&id_type, &value_type).is_some() {
+                        self.fix_literals(expr, &id_type);
+                    } else {
+                        eprintln!("Type Error: Mismatching types in declaration, expected {}, found {}", id_type, value_type);
+                        eprint!("let {} = ", id);
                         print_expression(expr);
-                        println!();
-                        println!("{:?}", type_collection);
+                        eprintln!();
                         return false;
                     }
                     // Set assigned to true
-                    self.table.update_var_assigned(id);
-                    return true;
-                } else {
-                    return true;
+                    self.table.update_var_assigned(*symbol);
+                    self.flow.assign(*symbol);
                 }
+                return true;
+            }
+            Statement::CompoundAssignment(expr1, operator, expr2, id) => {
+                // `a op= b` is checked as `a = a op b`
+                let value = Expresion::Binary(Box::new(expr1.clone()), operator.clone(), Box::new(expr2.clone()), *id);
+                return self.check_type(&Statement::Assignment(expr1.clone(), value));
             }
             Statement::Assignment(expr1, expr2) => {
-                let mut identifier: &str = "";
-                // Validate left expression is an identifier and push its type
-                if let Expresion::Identifier(id) = expr1 {
-                    identifier = id;
-                    let symbol = self.table.read_symbol(id);
-
-                    if let Some(symbol) = symbol {
-                        if let SymbolKind::Variable { data_type, .. } = &symbol.kind {
-                            if let Some(data_type) = data_type {
-                                type_collection.push(data_type.clone());
-                            } else {
-                                type_collection.push(DataType::Void);
-                            }
+                let mut identifier: Option<SymbolId> = None;
+                // A variable doesn't need a value to be assigned one, other places are checked
+                // like any expression
+                let target_type = if let Expresion::Identifier(id, reference, node) = expr1 {
+                    identifier = self.table.resolve(*reference);
+                    let symbol = identifier.map(|declaration| self.table.symbol(declaration));
+
+                    let target_type = match symbol.map(|symbol| &symbol.kind) {
+                        Some(SymbolKind::Variable { data_type, .. }) => data_type.clone().unwrap_or(DataType::Void),
+                        Some(_) => {
+                            eprintln!("Type Error: Can't assign value to function {}", id);
+                            return false;
                         }
-                        else {
-                            println!("Type Error: Can't assign value to function {}", id);
+                        None => {
+                            eprintln!("Type Error: Identifier '{}' not found in symbol table", id);
                             return false;
                         }
-                    } else {
-                        println!("Type Error: Identifier '{}' not found in symbol table", id);
+                    };
+                    self.types.insert(*node, target_type.clone());
+                    target_type
+                } else {
+                    let Some(target_type) = self.check_expression(expr1) else {
                         return false;
-                    }
-                }
+                    };
+                    target_type
+                };
 
-                type_collection = self.collect_types(expr2, type_collection);
-                if !self.check_collection(type_collection.clone()) {
-                    println!("Type Error: Mismatching types in assignment");
+                if !self.check_literal_range(&target_type, expr2) {
+                    return false;
+                }
+                let Some(value_type) = self.check_expression(expr2) else {
+                    return false;
+                };
+                // The first value assigned to a variable declared without type nor value gives its type
+                if let (Some(declaration), Expresion::Identifier(_, reference, node), DataType::Void) = (identifier, expr1, &target_type) {
+                    self.infer(declaration, &value_type, *reference, expr2);
+                    self.types.insert(*node, settle(&value_type));
+                    self.table.update_var_assigned(declaration);
+                    self.flow.assign(declaration);
+                    return true;
+                }
+                if unify(&target_type, &value_type).is_none() {
+                    eprintln!("Type Error: Mismatching types in assignment, expected {}, found {}", target_type, value_type);
                     print_expression(expr1);
-                    print!("= ");
+                    eprint!("= ");
                     print_expression(expr2);
-                    println!();
-                    println!("{:?}", type_collection);
+                    eprintln!();
+                    // Point at where the expected type came from too
+                    if let Some((line, value)) = identifier.and_then(|declaration| self.inferred.get(&declaration)) {
+                        eprintln!("Note: The type {} was inferred at line {} from", target_type, line);
+                        print_expression(value);
+                        eprintln!();
+                    }
                     return false;
                 }
+                self.fix_literals(expr2, &target_type);
                 // Set assigned to true
-                self.table.update_var_assigned(identifier);
+                if let Some(identifier) = identifier {
+                    self.table.update_var_assigned(identifier);
+                    self.flow.assign(identifier);
+                }
                 return true;
             }
             Statement::ExpressionStatement(expr) => {
-                type_collection = self.collect_types(expr, type_collection);
-                if !self.check_collection(type_collection.clone()) {
-                    println!("Type Error: Mismatching types in expression statement");
-                    print_expression(expr);
-                    println!();
-                    println!("{:?}", type_collection);
-                    return false;
-                }
-                return true;
+                return self.check_expression(expr).is_some();
+            }
+        }
+    }
+
+    fn check_block(&mut self, body: &[Statement]) -> bool {
+        // Check until a type error is found, an empty block is always valid
+        for statement in body {
+            if !self.check_type(statement) {
+                return false;
             }
         }
+        true
     }
 
-    fn collect_types(&self, expr: &Expresion, type_collection: Vec<DataType>) -> Vec<DataType> {
-        let mut type_collection = type_collection;
-        
+    // Type of the expression, also recorded in `types`. A type error is reported where
+    // it's found and gives None, the expressions containing it just pass it on
+    fn check_expression(&mut self, expr: &Expresion) -> Option<DataType> {
+        let data_type = self.expression_type(expr)?;
+        self.types.insert(expr.id(), settle(&data_type));
+        Some(data_type)
+    }
+
+    fn expression_type(&mut self, expr: &Expresion) -> Option<DataType> {
         match expr {
-            // Collect the type of the innermost expressions
-            Expresion::Binary(left, token, right) => {
-                let mut left_collection: Vec<DataType> = Vec::new();
-                let left_type: DataType;
-                let mut right_collection: Vec<DataType> = Vec::new();
-                let right_type: DataType;
-                let mut bin_type: Vec<DataType> = Vec::new();
-
-                // The idea is to recursively collect the types of the expressions, so that each pair 
-                // of expressions are added to the collection as a single type if they're valid.
-
-                // WARNING: This recycles a lot of code, but it's necessary as the logic for each type of
-                // binary expression is different. This could be refactored into a separate function, but
-                // it would be harder to read and maintain.
-                match token {
-                    Token::Operator(_) => {
-                        left_collection = self.collect_types(left, left_collection);
-                        // Continue only if the left expression checks out and only has numbers
-                        if (&left_collection[0] == &DataType::Integer || &left_collection[0] == &DataType::Float) &&
-                            self.check_collection(left_collection.clone()) {
-                                left_type = left_collection[0].clone();
+            Expresion::Literal(literal, _) => {
+                let data_type = match literal {
+                    Literal::Number(Number::Integer(_)) => DataType::IntLiteral,
+                    Literal::Number(Number::Float(..)) => DataType::Float,
+                    Literal::String(_) => DataType::String,
+                    Literal::Boolean(_) => DataType::Boolean,
+                    Literal::Char(_) => DataType::Char,
+                };
+                Some(data_type)
+            }
+            Expresion::Identifier(id, reference, _) => {
+                // Validate the identifier is bound to a variable with a value
+                match self.collect_id_type(*reference) {
+                    // If it's undefined, the resolver couldn't bind it to a declaration
+                    DataType::Undefined => error(format!("Identifier '{}' not found in symbol table", id), expr),
+                    _ if self.is_function(*reference) => error(format!("'{}' is a function, call it with {}(...)", id, id), expr),
+                    _ if !self.check_assignment(*reference) => {
+                        let path = self.table.resolve(*reference).and_then(|declaration| self.flow.unset_paths.get(&declaration));
+                        let Some(path) = path else {
+                            return error(format!("Identifier '{}' has no value assigned", id), expr);
+                        };
+                        let line = self.table.symbol(*reference).occurrence;
+                        eprintln!("Type Error: Identifier '{}' at line {} is possibly unassigned, it has no value when", id, line);
+                        print_path(path);
+                        None
+                    }
+                    var_type => Some(var_type),
+                }
+            }
+            Expresion::Binary(left, operator, right, id) => {
+                let left_type = self.check_expression(left)?;
+                let right_type = self.check_expression(right)?;
+                match operator {
+                    Token::Operator(op) => {
+                        if !is_numeric(&left_type) || !is_numeric(&right_type) {
+                            return error(format!("Non-numeric types {} and {} in arithmetic operation", left_type, right_type), expr);
                         }
-                        else {
-                            println!("Type Error: Non-numeric types in arithmetic operation");
-                            bin_type.push(DataType::Void);
-                            return bin_type;
+                        // Bitwise operations only make sense on integers
+                        let bitwise = matches!(op.as_str(), "&" | "|" | "^" | "<<" | ">>");
+                        if bitwise && !(may_be_integer(&left_type) && may_be_integer(&right_type)) {
+                            return error(format!("Non-integer types {} and {} in bitwise operation", left_type, right_type), expr);
                         }
 
-                        right_collection = self.collect_types(right, right_collection);
-                        // Continue only if the right expression checks out and only has numbers
-                        if (&right_collection[0] == &DataType::Integer || &right_collection[0] == &DataType::Float) &&
-                            self.check_collection(right_collection.clone()) {
-                                right_type = right_collection[0].clone();
-                        } else {
-                            println!("Type Error: Non-numeric types in arithmetic operation");
-                            bin_type.push(DataType::Void);
-                            return bin_type;
+                        // A shift keeps the type of the shifted value, whatever the type of the amount
+                        if op == "<<" || op == ">>" {
+                            return Some(left_type);
                         }
-
-                        if left_type == right_type {
-                            bin_type.push(left_type);
-                        } else {
-                            bin_type.push(DataType::Void);
+                        let Some(result) = self.promote(&left_type, &right_type, *id) else {
+                            return error(format!("Mismatching types {} and {} in arithmetic operation", left_type, right_type), expr);
+                        };
+                        self.fix_literals(left, &result);
+                        self.fix_literals(right, &result);
+                        Some(result)
+                    }
+                    Token::LogicalOperator(op) if op == "&&" || op == "||" => {
+                        if left_type != DataType::Boolean || right_type != DataType::Boolean {
+                            return error(format!("Non-boolean types {} and {} in boolean operation", left_type, right_type), expr);
                         }
+                        Some(DataType::Boolean)
                     }
                     Token::LogicalOperator(op) => {
-                        // Collection for ops like && is different to ==, !=, etc.
-                        match op.as_str() {
-                            "||" | "&&" => {
-                                // Same logic as arithmetic operations, but allows and returns boolean expressions exclusively
-                                left_collection = self.collect_types(left, left_collection);
-                                // Continue only if the left expression checks out and only has numbers
-                                if &left_collection[0] == &DataType::Boolean && 
-                                    self.check_collection(left_collection.clone()) {
-                                        left_type = left_collection[0].clone();
-                                }
-                                else {
-                                    println!("Type Error: Non-boolean types in boolean operation");
-                                    bin_type.push(DataType::Void);
-                                    return bin_type;
-                                }
-
-                                right_collection = self.collect_types(right, right_collection);
-                                // Continue only if the right expression checks out and only has numbers
-                                if &right_collection[0] == &DataType::Boolean && 
-                                    self.check_collection(right_collection.clone()) {
-                                        right_type = right_collection[0].clone();
-                                } else {
-                                    println!("Type Error: Non-boolean types in boolean operation");
-                                    bin_type.push(DataType::Void);
-                                    return bin_type;
-                                }
-
-                                if left_type == right_type {
-                                    bin_type.push(DataType::Boolean);
-                                } else {
-                                    bin_type.push(DataType::Void);
-                                }
-                            }
-                            _ => {
-                                // Same logic as arithmetic operations, but returning bool
-                                // Allow only boolean expressions with numeric expressions
-                                left_collection = self.collect_types(left, left_collection);
-                                // Continue only if the left expression checks out and only has numbers
-                                if (&left_collection[0] == &DataType::Integer || &left_collection[0] == &DataType::Float) &&
-                                    self.check_collection(left_collection.clone()) {
-                                        left_type = left_collection[0].clone();
-                                }
-                                else {
-                                    println!("Type Error: Non-numeric types in boolean comparison");
-                                    bin_type.push(DataType::Void);
-                                    return bin_type;
-                                }
-
-                                right_collection = self.collect_types(right, right_collection);
-                                // Continue only if the right expression checks out and only has numbers
-                                if (&right_collection[0] == &DataType::Integer || &right_collection[0] == &DataType::Float) &&
-                                    self.check_collection(right_collection.clone()) {
-                                        right_type = right_collection[0].clone();
-                                } else {
-                                    println!("Type Error: Non-numeric types in boolean comparison");
-                                    bin_type.push(DataType::Void);
-                                    return bin_type;
-                                }
-
-                                if left_type == right_type {
-                                    bin_type.push(DataType::Boolean);
-                                } else {
-                                    bin_type.push(DataType::Void);
-                                }
-                            }
+                        // Only numbers and chars can be compared, bools just for equality
+                        let equality = op == "==" || op == "!=";
+                        let comparable = |data_type: &DataType| is_comparable(data_type) || (equality && *data_type == DataType::Boolean);
+                        if !comparable(&left_type) || !comparable(&right_type) {
+                            return error(format!("Non-numeric types {} and {} in boolean comparison", left_type, right_type), expr);
                         }
-                    }
-                    _ => {}
+                        let Some(operands) = self.promote(&left_type, &right_type, *id) else {
+                            return error(format!("Mismatching types {} and {} in boolean comparison", left_type, right_type), expr);
+                        };
+                        self.fix_literals(left, &operands);
+                        self.fix_literals(right, &operands);
+                        Some(DataType::Boolean)
+                    }
+                    _ => error(format!("Unknown operator {}", operator), expr),
                 }
-                return bin_type;
             }
-            // WARNING: Array and Tuples share the collection of each expression,
-            // which could be refactored if it weren't necessary to shortcircuit
-            // the complete evaluation of this function if a type check fails
-            Expresion::Array(elements) => {
-                let mut arr_collection: Vec<DataType> = Vec::new();
-                // Collect and validate the type of each expression in the array
+            Expresion::Array(elements, _) => {
+                // Every element has to have the same type
+                let mut element_type: Option<DataType> = None;
                 for element in elements {
-                    let mut expr_coll: Vec<DataType> = Vec::new();
-                    expr_coll = self.collect_types(element, expr_coll);
-                    if self.check_collection(expr_coll.clone()) {
-                        arr_collection.push(expr_coll[0].clone());
-                    }
-                    else {
-                        // Shortcircuit the evaluation if a type error is found
-                        arr_collection.push(DataType::Void);
-                        return arr_collection;
-                    }
+                    let data_type = self.check_expression(element)?;
+                    element_type = match element_type {
+                        None => Some(data_type),
+                        Some(current) => match unify(&current, &data_type) {
+                            Some(common) => Some(common),
+                            None => return error(format!("Mismatching types {} and {} in array", current, data_type), expr),
+                        },
+                    };
                 }
-
-                // Validate the type of the array contents
-                if !self.check_collection(arr_collection.clone()) {
-                    println!("Type Error: Mismatching types in array");
-                    println!("{:?}", arr_collection);
-                    return arr_collection;
-                }
-
-                // Push the array type with its contained type
-                type_collection.push(DataType::Array(Box::new(arr_collection[0].clone()), elements.len() as i32));
-            }
-            // TODO: Tuples' types need to be validated as well
-            Expresion::Tuple(elements) => {
-                let mut tup_collection: Vec<DataType> = Vec::new();
+                let Some(element_type) = element_type else {
+                    return error("Can't infer the type of an empty array".to_string(), expr);
+                };
                 for element in elements {
-                    // Validate each element in the tuple
-                    let mut expr_coll: Vec<DataType> = Vec::new();
-                    expr_coll = self.collect_types(element, expr_coll);
-                    if self.check_collection(expr_coll.clone()) {
-                        tup_collection.push(expr_coll[0].clone());
-                    }
-                    else {
-                        // Shortcircuit the evaluation if a type error is found
-                        tup_collection.push(DataType::Void);
-                        return tup_collection;
-                    }
-                }
-
-                // Push the tuple type with its contained types
-                type_collection.push(DataType::Tuple(tup_collection));
-            }
-            Expresion::Unary(op, expr) => {
-                let mut expr_collection: Vec<DataType> = Vec::new();
-                // Validate token type
-                if &Token::Operator("-".to_string()) == op {
-                    expr_collection = self.collect_types(expr, expr_collection);
-                    // Return void if the type isn't a number
-                    for data_type in expr_collection.clone() {
-                        if data_type != DataType::Integer && data_type != DataType::Float {
-                            println!("Type Error: Non-numeric type in unary operation");
-                            expr_collection.push(DataType::Void);
-                            return expr_collection;
-                        }
-                    }
-                } else if &Token::Operator("!".to_string()) == op {
-                    type_collection = self.collect_types(expr, type_collection);
-                    // Return void if the type isn't a boolean
-                    for data_type in type_collection.clone() {
-                        if data_type != DataType::Boolean {
-                            println!("Type Error: Non-boolean type in unary operation");
-                            type_collection.push(DataType::Void);
-                            return type_collection;
-                        }
-                    }
-                } else {
-                    type_collection.push(DataType::Void);
-                    return type_collection;
+                    self.fix_literals(element, &element_type);
                 }
+                Some(DataType::Array(Box::new(element_type), elements.len() as i64))
             }
-            Expresion::Range(start, end, _) => {
-                type_collection = self.collect_types(start, type_collection);
-                type_collection = self.collect_types(end, type_collection);
-
-                // Return void if the types aren't integers
-                for data_type in type_collection.clone() {
-                    if data_type != DataType::Integer {
-                        println!("Type Error: Non-integer type in range");
-                        type_collection.push(DataType::Void);
-                        return type_collection;
-                    }
+            Expresion::Tuple(elements, _) => {
+                let mut types = Vec::new();
+                for element in elements {
+                    types.push(self.check_expression(element)?);
                 }
+                Some(DataType::Tuple(types))
             }
-
-            // Collect type of the actual terminal expression
-            Expresion::Literal(literal) => {
-                match literal {
-                    Literal::Number(number) => {
-                        match number {
-                            Number::Integer(_) => {
-                                type_collection.push(DataType::Integer);
-                            }
-                            Number::Float(_) => {
-                                type_collection.push(DataType::Float);
+            Expresion::Unary(op, operand, _) => {
+                let data_type = self.check_expression(operand)?;
+                match op.to_string().as_str() {
+                    "-" => {
+                        if !is_numeric(&data_type) {
+                            return error(format!("Non-numeric type {} in unary operation", data_type), expr);
+                        }
+                        if let DataType::Sized(int_type) = data_type {
+                            if !int_type.signed() {
+                                return error(format!("Cannot negate a value of unsigned type {}", int_type.name()), expr);
                             }
                         }
                     }
-                    Literal::String(_) => {
-                        type_collection.push(DataType::String);
+                    // `!` is a logical not on booleans and a bitwise not on integers
+                    "!" if data_type != DataType::Boolean && !is_integer(&data_type) => {
+                        return error(format!("Non-boolean or integer type {} in unary operation", data_type), expr);
                     }
-                    Literal::Boolean(_) => {
-                        type_collection.push(DataType::Boolean);
+                    "~" if !may_be_integer(&data_type) => {
+                        return error(format!("Non-integer type {} in bitwise not", data_type), expr);
                     }
+                    "!" | "~" => {}
+                    _ => return error(format!("Unknown operator {}", op), expr),
                 }
+                Some(data_type)
             }
-            // TODO: Need to validate it's in the same scope as the expression
-            // like var_declaration.scope_id <= var_use.scope_id -> True | This won't work, because that'd make it available for all further scopes
-            Expresion::Identifier(id) => {
-                // Collect the type of the identifier if it's a variable
-                let var_type = self.collect_id_type(id);
-                let param_type: DataType;
-                let assigned: bool;
-
-                // Validate the type of the identifier
-                match var_type {
-                    // If it's undefined, it's not a variable, so check if it's a param
-                    DataType::Undefined => {
-                        param_type = self.collect_param_type(id);
-                        match param_type {
-                            // If it's still undefined, it's not a variable or a param
-                            DataType::Undefined => {
-                                println!("Type Error: Identifier '{}' not found in symbol table", id);
-                                type_collection.push(DataType::Void);
-                            }
-                            // If it's a param, push the param's type if it's been assigned
-                            _ => {
-                                type_collection.push(param_type)
-                            }
-                        }
-                    }
-                    _ => {
-                        assigned = self.check_assignment(id);
-                        if assigned {
-                            type_collection.push(var_type);
-                        } else {
-                            println!("Type Error: Identifier '{}' has no value assigned", id);
-                            type_collection.push(DataType::Void);
-                        }
-                    }
+            Expresion::Range(start, end, _, _) => {
+                let start_type = self.check_expression(start)?;
+                let end_type = self.check_expression(end)?;
+                if !may_be_integer(&start_type) || !may_be_integer(&end_type) {
+                    return error(format!("Non-integer types {} and {} in range", start_type, end_type), expr);
                 }
+                let Some(bounds) = unify(&start_type, &end_type) else {
+                    return error(format!("Mismatching types {} and {} in range", start_type, end_type), expr);
+                };
+                self.fix_literals(start, &bounds);
+                self.fix_literals(end, &bounds);
+                Some(DataType::Range(Box::new(bounds)))
             }
-
-            // Collect the type of the actual terminal expression, but need to validate innermost expressions
-            Expresion::FnCall(name, args) => {
-                // Validate if the function exists in the symbol table
-                let fn_type = self.collect_id_type(name);
-
-                // Push the function's type
-                match fn_type {
-                    DataType::Undefined => {
-                        println!("Type Error: Function '{}' not found in symbol table", name);
-                        type_collection.push(DataType::Void);
+            Expresion::FnCall(name, args, reference, _) => {
+                // Functions that aren't declared in the program come from the target language,
+                // just their arguments are checked and what they give is left to python
+                if self.table.resolve(*reference).is_none() {
+                    for arg in args {
+                        self.check_expression(arg)?;
+                    }
+                    return Some(DataType::Dynamic);
+                }
+                let kind = self.table.resolve(*reference).map(|declaration| self.table.symbol(declaration).kind.clone());
+                let (fn_type, parameters, param_types) = match kind {
+                    Some(SymbolKind::Function { data_type, parameters, param_types }) => (data_type, parameters, param_types),
+                    Some(_) => {
+                        return match self.collect_id_type(*reference) {
+                            DataType::Void => error(format!("'{}' is a variable, not a function", name), expr),
+                            var_type => error(format!("'{}' is a variable of type {}, not a function", name, var_type), expr),
+                        };
+                    }
+                    None => return error(format!("Function '{}' not found in symbol table", name), expr),
+                };
+
+                if args.len() != parameters.len() {
+                    let signature = parameters.iter().zip(&param_types)
+                        .map(|(param, data_type)| format!("{}: {}", param, data_type))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    let plural = if parameters.len() == 1 { "" } else { "s" };
+                    return error(format!("Function '{}({})' expected {} argument{}, found {}", name, signature, parameters.len(), plural, args.len()), expr);
+                }
+                // Validate the arguments match the function's parameters
+                for ((param, param_type), arg) in parameters.iter().zip(&param_types).zip(args) {
+                    if !self.check_literal_range(param_type, arg) {
+                        return None;
                     }
-                    _ => {
-                        // Validate the types of the arguments
-                        if let Some(params) = self.table.get_params(name) {
-                            let mut arg_types: Vec<DataType> = Vec::new();
-
-                            for arg in args {
-                                let mut arg_collection: Vec<DataType> = Vec::new();
-                                arg_collection = self.collect_types(arg, arg_collection);
-                                if !self.check_collection(arg_collection.clone()) {
-                                    return arg_collection;
-                                }
-                                arg_types.push(arg_collection[0].clone());
-                            }
-                            // Validate the arguments match the function's parameters
-                            for i in 0..params.len() {
-                                if params[i] != arg_types[i] {
-                                    println!("Type Error: Mismatching arguments in function call");
-                                    type_collection.push(DataType::Void);
-                                    return type_collection;
-                                }
-                            }
-
-                            type_collection.push(fn_type);
-                        }
+                    let arg_type = self.check_expression(arg)?;
+                    if unify(param_type, &arg_type).is_none() {
+                        return error(format!("Mismatching argument for parameter '{}' of '{}', expected {}, found {}", param, name, param_type, arg_type), arg);
                     }
-                }    
+                    self.fix_literals(arg, param_type);
+                }
+                Some(fn_type.unwrap_or(DataType::Void))
             }
-            Expresion::Index(array, index) => {
+            Expresion::Index(array, index, _) => {
                 // Validate the index type
-                let mut index_collection: Vec<DataType> = Vec::new();
-                index_collection = self.collect_types(index, index_collection);
-
-                if index_collection.len() == 1 && index_collection[0] == DataType::Integer {
-                    // Get the type of the array, if it exists
-                    if let Expresion::Identifier(id) = &**array {
-                        let array_type = self.collect_id_type(id);
-                        if let DataType::Array(data_type, _) = array_type {
-                            type_collection.push(*data_type);
-                        } else {
-                            println!("Type Error: Identifier '{}' is not an array", id);
-                            type_collection.push(DataType::Void);
+                let index_type = self.check_expression(index)?;
+                if !may_be_integer(&index_type) {
+                    return error(format!("Non-integer type {} in array index", index_type), expr);
+                }
+                // Arrays give their elements and strings their chars
+                match self.check_expression(array)? {
+                    // Constant indexes are checked against the size of the array
+                    DataType::Array(data_type, size) => match literal_value(index) {
+                        Some(value) if value < 0 || value >= size as i128 => {
+                            error(format!("Index {} out of bounds for an array of length {}", value, size), expr)
                         }
-                    }
-                    
-                } else {
-                    println!("Type Error: Non-integer type in array index");
-                    type_collection.push(DataType::Void);
-                    return type_collection;
+                        _ => Some(*data_type),
+                    },
+                    DataType::String => Some(DataType::Char),
+                    DataType::Dynamic => Some(DataType::Dynamic),
+                    data_type => error(format!("Cannot index into a value of type {}", data_type), expr),
                 }
             }
-            Expresion::TupleIndex(expr, index) => {
+            Expresion::TupleIndex(tuple, index, _) => {
                 // Get the ith type of the tuple, if it exists
-                if let Expresion::Identifier(id) = &**expr {
-                    let tup_type = self.collect_id_type(id);
-                    if let DataType::Tuple(data_type) = tup_type {
-                        type_collection.push(data_type[*index].clone());
-                    } else {
-                        println!("Type Error: Identifier '{}' is not an array", id);
-                        type_collection.push(DataType::Void);
-                    }
+                match self.check_expression(tuple)? {
+                    DataType::Tuple(types) if *index < types.len() => Some(types[*index].clone()),
+                    DataType::Dynamic => Some(DataType::Dynamic),
+                    data_type => error(format!("No element {} in a value of type {}", index, data_type), expr),
+                }
+            }
+            Expresion::Member(object, member, _) => {
+                let data_type = self.check_expression(object)?;
+                if data_type == DataType::Dynamic {
+                    return Some(DataType::Dynamic);
+                }
+                error(format!("No member '{}' in a value of type {}", member, data_type), expr)
+            }
+            Expresion::Cast(value, target, _) => {
+                let data_type = self.check_expression(value)?;
+                // Integer literals cast to char have to be valid u8 values
+                if *target == DataType::Char && !self.check_literal_range(&DataType::Sized(IntType::U8), value) {
+                    return None;
+                }
+                if !castable(&data_type, target) {
+                    return error(format!("Cannot cast {} to {}", data_type, target), expr);
                 }
+                Some(target.clone())
+            }
+        }
+    }
+
+    // Common type of the operands of an arithmetic operation or comparison. An `int` meeting a
+    // float is the only implicit conversion, the integer side is recorded in `promotions`
+    fn promote(&mut self, left: &DataType, right: &DataType, id: NodeId) -> Option<DataType> {
+        let promotion = match (left, right) {
+            (DataType::Integer | DataType::IntLiteral, DataType::Float) => Promotion::Left,
+            (DataType::Float, DataType::Integer | DataType::IntLiteral) => Promotion::Right,
+            _ => return unify(left, right),
+        };
+        self.promotions.insert(id, promotion);
+        Some(DataType::Float)
+    }
 
+    // Integer literals take their type from where they're used. Once `expr` is known to have
+    // `data_type`, the literals in it and the arithmetic built from them are recorded with it
+    fn fix_literals(&mut self, expr: &Expresion, data_type: &DataType) {
+        match (expr, data_type) {
+            (Expresion::Literal(Literal::Number(Number::Integer(_)), id), data_type) if is_integer(data_type) => {
+                self.types.insert(*id, settle(data_type));
+            }
+            // only arithmetic on literals is still `int`, anything else already has its type
+            (Expresion::Unary(_, operand, id), data_type) if is_integer(data_type) && self.types.get(id) == Some(&DataType::Integer) => {
+                self.types.insert(*id, settle(data_type));
+                self.fix_literals(operand, data_type);
             }
-            Expresion::Member(_, _) => {
-                // If this is about tuples, then the previous match will handle it, right?
+            (Expresion::Binary(left, Token::Operator(op), right, id), data_type) if is_integer(data_type) && self.types.get(id) == Some(&DataType::Integer) => {
+                self.types.insert(*id, settle(data_type));
+                self.fix_literals(left, data_type);
+                // the amount of a shift doesn't take the type of the shifted value
+                if op != "<<" && op != ">>" {
+                    self.fix_literals(right, data_type);
+                }
+            }
+            (Expresion::Array(elements, id), DataType::Array(element_type, _)) => {
+                self.types.insert(*id, settle(data_type));
+                for element in elements {
+                    self.fix_literals(element, element_type);
+                }
             }
+            (Expresion::Tuple(elements, id), DataType::Tuple(types)) if elements.len() == types.len() => {
+                self.types.insert(*id, settle(data_type));
+                for (element, element_type) in elements.iter().zip(types) {
+                    self.fix_literals(element, element_type);
+                }
+            }
+            _ => {}
         }
-        return type_collection;
     }
 
-    fn collect_id_type(&self, id: &String) -> DataType {
+    fn collect_id_type(&self, id: SymbolId) -> DataType {
         // This function is used to get the type of a variable or function identifier,
         // which will return:
         // - The type of the variable or function
         // - Undefined if it isn't found
 
-        // Get the symbol if its a variable
-        let symbol = self.table.read_symbol(id);
+        // Get the declaration the identifier is bound to
+        let symbol = self.table.resolve(id).map(|declaration| self.table.symbol(declaration));
         if let Some(symbol) = symbol {
             // Get the type if it's a variable, the rest'll get ignored
             match &symbol.kind {
@@ -595,75 +670,347 @@ impl Semantic {
                         return data_type.clone();
                     }
                 }
+                SymbolKind::Reference { .. } => {}
             }
         }
         return DataType::Undefined;
     }
 
-    fn check_assignment(&self, id: &String) -> bool {
-        // This function is used to check wether a variable has been assigned a value or not
+    fn is_function(&self, id: SymbolId) -> bool {
+        let symbol = self.table.resolve(id).map(|declaration| self.table.symbol(declaration));
+        matches!(symbol.map(|symbol| &symbol.kind), Some(SymbolKind::Function { .. }))
+    }
 
-        // Get the symbol if its a variable
-        let symbol = self.table.read_symbol(id);
-        if let Some(symbol) = symbol {
-            // Get the assignment if it's a variable, the rest'll get ignored
-            match &symbol.kind {
-                SymbolKind::Variable { assigned, .. } => {
-                    return assigned.clone();
-                }
-                _ => {
-                    return false;
-                }
+    // Type of the elements a for loop goes over: the values of a range, the elements of an
+    // array or of a tuple of a single type, or the chars of a string. `enumerate(x)` pairs
+    // each element of `x` with its index
+    fn iteration_type(&mut self, iterable: &Expresion) -> Option<DataType> {
+        if let Expresion::FnCall(name, args, reference, _) = iterable {
+            if name == "enumerate" && args.len() == 1 && self.table.resolve(*reference).is_none() {
+                let element = self.iteration_type(&args[0])?;
+                return Some(DataType::Tuple(vec![DataType::Integer, element]));
             }
         }
-        return false;
+        let iterable_type = self.check_expression(iterable)?;
+        let element = match &iterable_type {
+            DataType::Range(data_type) | DataType::Array(data_type, _) => Some(*data_type.clone()),
+            DataType::String => Some(DataType::Char),
+            DataType::Tuple(types) => common_type(types),
+            DataType::Dynamic => Some(DataType::Dynamic),
+            _ => None,
+        };
+        if element.is_none() {
+            eprintln!("Type Error: Cannot iterate over a value of type {}, use a range, an array, a string or a tuple of a single type", iterable_type);
+            print_expression(iterable);
+            eprintln!();
+        }
+        element
     }
-    
-    fn collect_param_type(&self, id: &String) -> DataType {
-        // This function is used to get the type of a function parameter identifier,
-        // which will return:
-        // - The type of the parameter if it's a parameter
-        // - Undefined if it isn't found
 
-        // Get the symbol if its a function parameter
-        let symbols = self.table.get_all_symbols();
-        for symbol in symbols {
-            // Check the parameters of the functions
-            match &symbol.kind {
-                SymbolKind::Function { parameters, param_types, .. } => {
-                    for (i, param) in parameters.iter().enumerate() {
-                        if param == id {
-                            return param_types[i].clone();
-                        }
-                    }
-                }
-                _ => {}
+    // Gives each name of the pattern the type of the part of the value it takes, a tuple
+    // pattern only matches a tuple with as many elements
+    fn bind_pattern(&mut self, pattern: &Pattern, data_type: &DataType) -> bool {
+        match (pattern, data_type) {
+            (Pattern::Binding(_, id), _) => {
+                self.table.update_var_type(*id, settle(data_type));
+                self.table.update_var_assigned(*id);
+                self.flow.assign(*id);
+                true
+            }
+            (Pattern::Wildcard, _) => true,
+            (Pattern::Tuple(patterns), DataType::Tuple(types)) if patterns.len() == types.len() => {
+                patterns.iter().zip(types).all(|(pattern, data_type)| self.bind_pattern(pattern, data_type))
+            }
+            (Pattern::Tuple(patterns), DataType::Dynamic) => patterns.iter().all(|pattern| self.bind_pattern(pattern, data_type)),
+            (Pattern::Tuple(patterns), _) => {
+                let elements = if patterns.len() == 1 { "element" } else { "elements" };
+                eprintln!("Type Error: The pattern {} expects a tuple of {} {}, found {}", pattern, patterns.len(), elements, data_type);
+                false
             }
         }
-        DataType::Undefined
     }
 
-    fn check_collection(&self, type_collection: Vec<DataType>) -> bool {
-        let mut data_type= &DataType::Void;
+    // Gives a variable without annotation the type of its first value, `site` is the
+    // declaration or reference the value is given at
+    fn infer(&mut self, declaration: SymbolId, value_type: &DataType, site: SymbolId, value: &Expresion) {
+        let line = self.table.symbol(site).occurrence;
+        self.table.update_var_type(declaration, settle(value_type));
+        self.inferred.insert(declaration, (line, value.clone()));
+    }
 
-        // Check for different types in expression
-        for i in 0..type_collection.len() {
-            if i == 0 {
-                data_type = &type_collection[i];
-            } else {
-                if data_type != &type_collection[i]  {
-                    return false;
-                }
+    fn check_assignment(&self, id: SymbolId) -> bool {
+        // Whether a variable has a value on every path that reaches it, code after a return
+        // or a break is never reached so anything goes there
+        match self.table.resolve(id) {
+            Some(declaration) => self.flow.unreachable || self.flow.assigned.contains(&declaration),
+            None => false,
+        }
+    }
+
+    fn check_literal_range(&self, data_type: &DataType, expr: &Expresion) -> bool {
+        // Integer literals assigned to a fixed width type have to fit in it
+        let Some(value) = literal_value(expr) else {
+            return true;
+        };
+        if let DataType::Sized(int_type) = data_type {
+            let (min, max) = int_type.range();
+            if value < min || value > max {
+                eprintln!("Type Error: Literal {} out of range for type {}", value, int_type.name());
+                return false;
+            }
+        }
+        true
+    }
+}
+
+// Reports a type error, pointing at the expression it was found in
+fn error(message: String, expr: &Expresion) -> Option<DataType> {
+    eprintln!("Type Error: {}", message);
+    print_expression(expr);
+    eprintln!();
+    None
+}
+
+// Writes the branches taken in a path through the program, one per line
+fn print_path(path: &[Branch]) {
+    for branch in path {
+        match branch {
+            Branch::Condition(cond, taken) => {
+                eprint!("    the condition ");
+                print_expression(cond);
+                eprintln!("is {}", taken);
+            }
+            Branch::NoIterations(range) => {
+                eprint!("    the for loop over ");
+                print_expression(range);
+                eprintln!("doesn't run");
+            }
+            Branch::Break(line) => {
+                eprintln!("    a break ends the loop at line {}", line);
             }
         }
-        // TODO: This prints both errors, but it should only print the first one
+    }
+}
+
+// The type every one of the types unifies with, if there's any
+fn common_type(types: &[DataType]) -> Option<DataType> {
+    let (first, rest) = types.split_first()?;
+    rest.iter().try_fold(first.clone(), |common, data_type| unify(&common, data_type))
+}
+
+// Value of an integer literal, negated or not
+fn literal_value(expr: &Expresion) -> Option<i128> {
+    match expr {
+        Expresion::Literal(Literal::Number(Number::Integer(value)), _) => Some(*value),
+        Expresion::Unary(Token::Operator(op), operand, _) if op == "-" => literal_value(operand).map(|value| -value),
+        _ => None,
+    }
+}
+
+fn is_integer(data_type: &DataType) -> bool {
+    matches!(data_type, DataType::Integer | DataType::Sized(_) | DataType::IntLiteral)
+}
+
+// A dynamic value is only known when the program runs, it's taken where a number is but
+// not where a bool is, `!` and conditions need a cast to say which one it is
+fn may_be_integer(data_type: &DataType) -> bool {
+    is_integer(data_type) || *data_type == DataType::Dynamic
+}
+
+fn is_numeric(data_type: &DataType) -> bool {
+    may_be_integer(data_type) || *data_type == DataType::Float
+}
+
+// Chars compare by code point, like numbers
+fn is_comparable(data_type: &DataType) -> bool {
+    is_numeric(data_type) || *data_type == DataType::Char
+}
+
+// Conversions `as` can do: between numbers, from bool and char to integers, from u8 to char,
+// from integers, bools and chars to string, and from a type to itself
+fn castable(from: &DataType, to: &DataType) -> bool {
+    match (from, to) {
+        _ if unify(from, to).is_some() => true,
+        (from, to) if is_numeric(from) && is_numeric(to) => true,
+        (DataType::Boolean | DataType::Char, to) => is_integer(to) || *to == DataType::String,
+        (from, DataType::Char) => matches!(from, DataType::Sized(IntType::U8) | DataType::IntLiteral),
+        (from, DataType::String) => is_integer(from),
+        _ => false,
+    }
+}
 
-        // Validate no Void type in expression
-        if data_type == &DataType::Void {
-            println!("Type Error: Void type found in expression. Declare the value or use a valid type");
-            return false;
+// Common type of two types, integer literals take the type of the other side
+fn unify(left: &DataType, right: &DataType) -> Option<DataType> {
+    match (left, right) {
+        // a dynamic value is taken to have the type of the other side
+        (DataType::Dynamic, other) | (other, DataType::Dynamic) => Some(other.clone()),
+        (DataType::IntLiteral, other) | (other, DataType::IntLiteral) if is_integer(other) => Some(other.clone()),
+        (DataType::Array(left, left_size), DataType::Array(right, right_size)) if left_size == right_size => {
+            unify(left, right).map(|data_type| DataType::Array(Box::new(data_type), *left_size))
         }
-        
-        return true;
+        (DataType::Tuple(left), DataType::Tuple(right)) if left.len() == right.len() => {
+            left.iter().zip(right).map(|(left, right)| unify(left, right)).collect::<Option<Vec<_>>>().map(DataType::Tuple)
+        }
+        (DataType::Range(left), DataType::Range(right)) => unify(left, right).map(|data_type| DataType::Range(Box::new(data_type))),
+        _ if left == right => Some(left.clone()),
+        _ => None,
+    }
+}
+
+// Type a value ends up with, integer literals nothing else decided on are `int`
+fn settle(data_type: &DataType) -> DataType {
+    match data_type {
+        DataType::IntLiteral => DataType::Integer,
+        DataType::Array(data_type, size) => DataType::Array(Box::new(settle(data_type)), *size),
+        DataType::Tuple(types) => DataType::Tuple(types.iter().map(settle).collect()),
+        DataType::Range(data_type) => DataType::Range(Box::new(settle(data_type))),
+        _ => data_type.clone(),
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::resolver::Resolver;
+    use crate::sintax::Sintax;
+    use crate::table::UseType;
+
+    // Checks the program, the checker is returned to look at the flow it ended with
+    fn check(source: &str) -> (Semantic, bool) {
+        let mut parser = Sintax::new(Lexer::new(source));
+        parser.parse();
+        let mut resolver = Resolver::new(parser.table);
+        assert!(resolver.resolve(&parser.program));
+        let mut semantic = Semantic::new(parser.program, resolver.table);
+        let passed = semantic.semantic_check();
+        (semantic, passed)
+    }
+
+    // The branches of the path that leaves the variable without a value at the end of the program
+    fn unset_path(semantic: &Semantic, name: &str) -> Option<Vec<String>> {
+        let declaration = semantic.table.symbols.iter()
+            .find(|symbol| symbol.value == name && matches!(symbol.use_type, UseType::Declaration))?;
+        let path = semantic.flow.unset_paths.get(&declaration.id)?;
+        Some(path.iter().map(|branch| match branch {
+            Branch::Condition(Expresion::Identifier(condition, _, _), taken) => format!("{} is {}", condition, taken),
+            Branch::Condition(_, taken) => format!("condition {}", taken),
+            Branch::NoIterations(_) => "no iterations".to_string(),
+            Branch::Break(line) => format!("break at line {}", line),
+        }).collect())
+    }
+
+    fn identifier(name: &str) -> Expresion {
+        Expresion::Identifier(name.to_string(), 0, 0)
+    }
+
+    // The branch taken when the variable `c` is true or false
+    fn condition(taken: bool) -> Option<Branch> {
+        Some(Branch::Condition(identifier("c"), taken))
+    }
+
+    // Whether the path is the else side of the condition on `c`
+    fn is_else(branch: &Branch) -> bool {
+        matches!(branch, Branch::Condition(Expresion::Identifier(name, _, _), false) if name == "c")
+    }
+
+    #[test]
+    fn join_keeps_what_every_path_assigns() {
+        let mut then_flow = Flow::default();
+        then_flow.assign(1);
+        then_flow.assign(2);
+        let mut else_flow = Flow::default();
+        else_flow.assign(1);
+
+        let joined = Flow::join(&Flow::default(), vec![(then_flow, condition(true)), (else_flow, condition(false))]);
+        assert_eq!(joined.assigned, HashSet::from([1]));
+        assert!(matches!(&joined.unset_paths[&2][..], [branch] if is_else(branch)));
+        assert!(!joined.unreachable);
+    }
+
+    #[test]
+    fn join_ignores_paths_that_return() {
+        let mut then_flow = Flow::default();
+        then_flow.assign(1);
+        let returned = Flow { unreachable: true, ..Flow::default() };
+
+        let joined = Flow::join(&Flow::default(), vec![(then_flow, condition(true)), (returned.clone(), condition(false))]);
+        assert_eq!(joined.assigned, HashSet::from([1]));
+        assert!(joined.unset_paths.is_empty());
+
+        let joined = Flow::join(&Flow::default(), vec![(returned.clone(), condition(true)), (returned, condition(false))]);
+        assert!(joined.unreachable);
+    }
+
+    #[test]
+    fn join_extends_the_path_from_before_the_branch() {
+        let mut before = Flow::default();
+        before.unset_paths.insert(1, vec![Branch::NoIterations(identifier("items"))]);
+        let mut then_flow = before.clone();
+        then_flow.assign(1);
+
+        let joined = Flow::join(&before, vec![(then_flow, condition(true)), (before.clone(), condition(false))]);
+        assert!(matches!(&joined.unset_paths[&1][..], [Branch::NoIterations(_), branch] if is_else(branch)));
+
+        // Neither side assigns it, the path stays the one from before
+        let joined = Flow::join(&before, vec![(before.clone(), condition(true)), (before.clone(), condition(false))]);
+        assert!(matches!(joined.unset_paths[&1][..], [Branch::NoIterations(_)]));
+    }
+
+    #[test]
+    fn if_without_else_may_skip_the_assignment() {
+        let (semantic, passed) = check("let c = true;\nlet x: int;\nif (c) {\n    x = 1;\n}\n");
+        assert!(passed);
+        assert_eq!(unset_path(&semantic, "x"), Some(vec!["c is false".to_string()]));
+
+        let (_, passed) = check("let c = true;\nlet x: int;\nif (c) {\n    x = 1;\n}\nlet y = x;\n");
+        assert!(!passed);
+    }
+
+    #[test]
+    fn if_with_else_assigning_in_both_branches() {
+        let (semantic, passed) = check("let c = true;\nlet x: int;\nif (c) {\n    x = 1;\n} else {\n    x = 2;\n}\nlet y = x;\n");
+        assert!(passed);
+        assert_eq!(unset_path(&semantic, "x"), None);
+    }
+
+    #[test]
+    fn nested_ifs_record_every_condition() {
+        let (semantic, passed) = check("let a = true;\nlet b = true;\nlet x: int;\nif (a) {\n    if (b) {\n        x = 1;\n    }\n} else {\n    x = 2;\n}\n");
+        assert!(passed);
+        assert_eq!(unset_path(&semantic, "x"), Some(vec!["a is true".to_string(), "b is false".to_string()]));
+    }
+
+    #[test]
+    fn for_loop_may_not_run() {
+        let (semantic, passed) = check("let x: int;\nfor (i in 0..3) {\n    x = i;\n}\n");
+        assert!(passed);
+        assert_eq!(unset_path(&semantic, "x"), Some(vec!["no iterations".to_string()]));
+
+        let (_, passed) = check("let x: int;\nfor (i in 0..3) {\n    x = i;\n}\nlet y = x;\n");
+        assert!(!passed);
+    }
+
+    #[test]
+    fn break_out_of_loop_after_the_assignment() {
+        let (semantic, passed) = check("let x: int;\nloop {\n    x = 1;\n    break;\n}\nlet y = x;\n");
+        assert!(passed);
+        assert_eq!(unset_path(&semantic, "x"), None);
+    }
+
+    #[test]
+    fn break_out_of_loop_before_the_assignment() {
+        let (semantic, passed) = check("let c = true;\nlet x: int;\nloop {\n    if (c) {\n        break;\n    }\n    x = 1;\n    break;\n}\n");
+        assert!(passed);
+        assert_eq!(unset_path(&semantic, "x"), Some(vec!["break at line 3".to_string()]));
+
+        let (_, passed) = check("let c = true;\nlet x: int;\nloop {\n    if (c) {\n        break;\n    }\n    x = 1;\n    break;\n}\nlet y = x;\n");
+        assert!(!passed);
+    }
+
+    #[test]
+    fn code_after_return_is_not_checked() {
+        let (_, passed) = check("fn f(c: bool) -> int {\n    let x: int;\n    if (c) {\n        x = 1;\n    } else {\n        return 0;\n    }\n    return x;\n}\n");
+        assert!(passed);
+    }
+}
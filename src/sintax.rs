@@ -1,6 +1,8 @@
+use std::ops::RangeInclusive;
 use std::process::exit;
 use crate::visitor::{Visitable, Visitor};
 use crate::{Symbol, SymbolTable, UseType};
+use crate::table::{SymbolId, ScopeKind};
 use crate::lexer::{Lexer, Number, Token};
 
 #[derive(Debug, PartialEq, Clone)]
@@ -9,14 +11,113 @@ pub enum DataType {
     Float,
     String,
     Boolean,
+    Char,
     // Void is used for functions that do not return a value,
     // variables that have no type, and expressions with faulty typing
     Void,
     // Undefined is used for identifiers that have not been initialized
     Undefined,
-    Array(Box<DataType>,i32),
+    Array(Box<DataType>,i64),
     Tuple(Vec<DataType>),
     Identifier(String),
+    // Fixed width integers, `int` stays unbounded
+    Sized(IntType),
+    // Integer literals fit any integer type, they become `int` if nothing else decides
+    IntLiteral,
+    // `a..b` and `a..=b`, holds the type of the bounds
+    Range(Box<DataType>),
+    // Values from functions the program doesn't declare, python decides what they are
+    Dynamic,
+}
+
+// Types as they're written in Ruston, used in diagnostics
+impl std::fmt::Display for DataType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DataType::Integer => write!(f, "int"),
+            DataType::Float => write!(f, "float"),
+            DataType::String => write!(f, "string"),
+            DataType::Boolean => write!(f, "bool"),
+            DataType::Char => write!(f, "char"),
+            DataType::Void => write!(f, "void"),
+            DataType::Undefined => write!(f, "undefined"),
+            DataType::Array(data_type, size) => write!(f, "[{}; {}]", data_type, size),
+            DataType::Tuple(types) if types.len() == 1 => write!(f, "({},)", types[0]),
+            DataType::Tuple(types) => {
+                let types = types.iter().map(|data_type| data_type.to_string()).collect::<Vec<_>>();
+                write!(f, "({})", types.join(", "))
+            }
+            DataType::Identifier(name) => write!(f, "{}", name),
+            DataType::Sized(int_type) => write!(f, "{}", int_type.name()),
+            DataType::IntLiteral => write!(f, "{{integer}}"),
+            DataType::Range(data_type) => write!(f, "Range<{}>", data_type),
+            DataType::Dynamic => write!(f, "dynamic"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum IntType {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+}
+
+impl IntType {
+    pub fn from_name(name: &str) -> Option<IntType> {
+        match name {
+            "i8" => Some(IntType::I8),
+            "i16" => Some(IntType::I16),
+            "i32" => Some(IntType::I32),
+            "i64" => Some(IntType::I64),
+            "u8" => Some(IntType::U8),
+            "u16" => Some(IntType::U16),
+            "u32" => Some(IntType::U32),
+            "u64" => Some(IntType::U64),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            IntType::I8 => "i8",
+            IntType::I16 => "i16",
+            IntType::I32 => "i32",
+            IntType::I64 => "i64",
+            IntType::U8 => "u8",
+            IntType::U16 => "u16",
+            IntType::U32 => "u32",
+            IntType::U64 => "u64",
+        }
+    }
+
+    pub fn bits(&self) -> u32 {
+        match self {
+            IntType::I8 | IntType::U8 => 8,
+            IntType::I16 | IntType::U16 => 16,
+            IntType::I32 | IntType::U32 => 32,
+            IntType::I64 | IntType::U64 => 64,
+        }
+    }
+
+    pub fn signed(&self) -> bool {
+        matches!(self, IntType::I8 | IntType::I16 | IntType::I32 | IntType::I64)
+    }
+
+    // Smallest and largest values of the type, i128 fits the range of every type
+    pub fn range(&self) -> (i128, i128) {
+        let bits = self.bits();
+        if self.signed() {
+            (-(1 << (bits - 1)), (1 << (bits - 1)) - 1)
+        } else {
+            (0, (1 << bits) - 1)
+        }
+    }
 }
 
 
@@ -25,6 +126,7 @@ pub enum Literal {
     Number(Number),
     String(String),
     Boolean(bool),
+    Char(char),
 }
 
 
@@ -34,6 +136,7 @@ impl  Visitable for Literal{
             Literal::Number(number) => visitor.visit_number(number),
             Literal::String(string) => visitor.visit_string(string),
             Literal::Boolean(boolean) => visitor.visit_boolean(boolean),
+            Literal::Char(char) => visitor.visit_char(*char),
         }
     }
 }
@@ -42,19 +145,66 @@ impl  Visitable for Literal{
 
 
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Associativity {
+    Left,
+    Right,
+    // the operator can't be chained, `a < b < c` is an error
+    None,
+}
+
+// Infix operators from the loosest to the tightest binding, as in rules/ebnf.md.
+// The prefix `-`, `!` and `~` sit between `as` and `**`, so `-x as u8` is `(-x) as u8`
+// and `-2 ** 2` is `-(2 ** 2)` like in python
+const OPERATORS: &[(&[&str], u8, Associativity)] = &[
+    (&["..", "..="], 1, Associativity::None),
+    (&["||"], 2, Associativity::Left),
+    (&["&&"], 3, Associativity::Left),
+    (&["==", "!=", "<", ">", "<=", ">="], 4, Associativity::None),
+    (&["|"], 5, Associativity::Left),
+    (&["^"], 6, Associativity::Left),
+    (&["&"], 7, Associativity::Left),
+    (&["<<", ">>"], 8, Associativity::Left),
+    (&["+", "-"], 9, Associativity::Left),
+    (&["*", "/", "%"], 10, Associativity::Left),
+    (&["as"], 11, Associativity::Left),
+    (&["**"], 13, Associativity::Right),
+];
+
+const UNARY_PRECEDENCE: u8 = 12;
+
+const UNARY_OPERATORS: &[&str] = &["-", "!", "~"];
+
+// Precedence and associativity of the token if it's an infix operator
+fn binary_operator(token: &Token) -> Option<(u8, Associativity)> {
+    let text = match token {
+        Token::Operator(op) | Token::LogicalOperator(op) => op.clone(),
+        Token::Range | Token::RangeInclusive | Token::As => token.to_string(),
+        _ => return None,
+    };
+    OPERATORS.iter()
+        .find(|(operators, _, _)| operators.contains(&text.as_str()))
+        .map(|(_, precedence, associativity)| (*precedence, *associativity))
+}
+
+// Every expression gets a unique id, so later passes can attach data to it
+pub type NodeId = u32;
+
 #[derive(Debug, Clone)]
 pub enum Expresion {
-    Literal(Literal),
-    Identifier(String),
-    Binary(Box<Expresion>, Token, Box<Expresion>),
-    FnCall(String, Vec<Expresion>),
-    Tuple(Vec<Expresion>),
-    Array(Vec<Expresion>),
-    Index(Box<Expresion>, Box<Expresion>),
-    Member(Box<Expresion>, String),
-    TupleIndex(Box<Expresion>, usize),
-    Unary(Token, Box<Expresion>),
-    Range(Box<Expresion>, Box<Expresion>, bool), // bool indica si es inclusivo
+    Literal(Literal, NodeId),
+    // the symbol id is the reference recorded for this use of the name
+    Identifier(String, SymbolId, NodeId),
+    Binary(Box<Expresion>, Token, Box<Expresion>, NodeId),
+    FnCall(String, Vec<Expresion>, SymbolId, NodeId),
+    Tuple(Vec<Expresion>, NodeId),
+    Array(Vec<Expresion>, NodeId),
+    Index(Box<Expresion>, Box<Expresion>, NodeId),
+    Member(Box<Expresion>, String, NodeId),
+    TupleIndex(Box<Expresion>, usize, NodeId),
+    Unary(Token, Box<Expresion>, NodeId),
+    Range(Box<Expresion>, Box<Expresion>, bool, NodeId), // bool indica si es inclusivo
+    Cast(Box<Expresion>, DataType, NodeId), // expr as type
 }
 
 
@@ -65,32 +215,72 @@ impl Expresion {
         match token {
             Token::Number(value) => match value {
                 Number::Integer(value) => Some(Literal::Number(Number::Integer(value))),
-                Number::Float(value) => Some(Literal::Number(Number::Float(value))),
+                Number::Float(value, text) => Some(Literal::Number(Number::Float(value, text))),
             },
             Token::String(value) => Some(Literal::String(value)),
+            Token::Char(value) => Some(Literal::Char(value)),
             Token::True => Some(Literal::Boolean(true)),
             Token::False => Some(Literal::Boolean(false)),
             _ => None,
         }
     }
-    
+
+    // whether the expression names a place that can be assigned to
+    fn is_place(expr: &Expresion) -> bool {
+        matches!(expr, Expresion::Identifier(..) | Expresion::Index(..) | Expresion::TupleIndex(..) | Expresion::Member(..))
+    }
+
+    // the literal `-expr` folds to, if `expr` is a number that can be negated
+    fn negate_literal(expr: &Expresion) -> Option<Expresion> {
+        match expr {
+            Expresion::Literal(Literal::Number(Number::Integer(value)), id) => {
+                value.checked_neg().map(|value| Expresion::Literal(Literal::Number(Number::Integer(value)), *id))
+            }
+            Expresion::Literal(Literal::Number(Number::Float(value, text)), id) => {
+                let text = match text.strip_prefix('-') {
+                    Some(positive) => positive.to_string(),
+                    None => format!("-{}", text),
+                };
+                Some(Expresion::Literal(Literal::Number(Number::Float(-value, text)), *id))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn id(&self) -> NodeId {
+        match self {
+            Expresion::Literal(_, id)
+            | Expresion::Identifier(_, _, id)
+            | Expresion::Binary(_, _, _, id)
+            | Expresion::FnCall(_, _, _, id)
+            | Expresion::Tuple(_, id)
+            | Expresion::Array(_, id)
+            | Expresion::Index(_, _, id)
+            | Expresion::Member(_, _, id)
+            | Expresion::TupleIndex(_, _, id)
+            | Expresion::Unary(_, _, id)
+            | Expresion::Range(_, _, _, id)
+            | Expresion::Cast(_, _, id) => *id,
+        }
+    }
 }
 
 impl Visitable for Expresion{
     
         fn  accept(&self, visitor: &mut dyn Visitor) -> String {
             match self {
-                Expresion::Literal(literal) => visitor.visit_literal(literal),
-                Expresion::Identifier(identifier) => visitor.visit_identifier(identifier),
-                Expresion::Binary(left, operator, right) => visitor.visit_binary(left, operator, right),
-                Expresion::FnCall(name, args) => visitor.visit_fn_call(name, args),
-                Expresion::Array(elements) => visitor.visit_array(elements),
-                Expresion::Unary(operator, operand) => visitor.visit_unary(operator, operand),
-                Expresion::Range(start, end, inclusive) => visitor.visit_range(start, end, *inclusive),
-                Expresion::Tuple(elements) => visitor.visit_tuple(elements),
-                Expresion::Index(array, index) => visitor.visit_index(array, index),
-                Expresion::Member(object, member) => visitor.visit_member(object, member),
-                Expresion::TupleIndex(tuple, index) => visitor.visit_tuple_index(tuple, *index),
+                Expresion::Literal(literal, _) => visitor.visit_literal(literal),
+                Expresion::Identifier(identifier, reference, _) => visitor.visit_identifier(identifier, *reference),
+                Expresion::Binary(left, operator, right, id) => visitor.visit_binary(left, operator, right, *id),
+                Expresion::FnCall(name, args, reference, _) => visitor.visit_fn_call(name, args, *reference),
+                Expresion::Array(elements, _) => visitor.visit_array(elements),
+                Expresion::Unary(operator, operand, id) => visitor.visit_unary(operator, operand, *id),
+                Expresion::Range(start, end, inclusive, _) => visitor.visit_range(start, end, *inclusive),
+                Expresion::Tuple(elements, _) => visitor.visit_tuple(elements),
+                Expresion::Index(array, index, _) => visitor.visit_index(array, index),
+                Expresion::Member(object, member, _) => visitor.visit_member(object, member),
+                Expresion::TupleIndex(tuple, index, _) => visitor.visit_tuple_index(tuple, *index),
+                Expresion::Cast(expr, data_type, _) => visitor.visit_cast(expr, data_type),
                 
                 }
             }
@@ -104,13 +294,18 @@ impl Visitable for Expresion{
 #[derive(Debug, Clone)]
 pub enum Statement {
     ExpressionStatement(Expresion),
-    Declaration(String, Option<Expresion>),
+    Declaration(String, Option<Expresion>, SymbolId),
+    // let (a, b) = value;
+    Destructure(Pattern, Expresion),
     Assignment(Expresion, Expresion),
+    // place op= value, checked and typed as `place = place op value`
+    CompoundAssignment(Expresion, Token, Expresion, NodeId),
     If(Expresion, Vec<Statement>, Option< Box<Statement>>, u32),
     Loop(Vec<Statement>, u32),
-    For(String,Expresion,Vec<Statement>, u32),
+    For(Pattern,Expresion,Vec<Statement>, u32),
     FnDeclaration(String, Vec<String>, Vec<Statement>, u32),
     Return(Option<Expresion>),
+    Break,
 }
 
 
@@ -119,13 +314,16 @@ impl Visitable for  Statement{
     fn  accept(&self, visitor: &mut dyn Visitor) -> String {
         match self {
             Statement::ExpressionStatement(expression) => visitor.visit_expression_statement(expression),
-            Statement::Declaration(id, expression) => visitor.visit_declaration(id, expression),
+            Statement::Declaration(id, expression, symbol) => visitor.visit_declaration(id, expression, *symbol),
+            Statement::Destructure(pattern, expression) => visitor.visit_destructure(pattern, expression),
             Statement::Assignment(left, right) => visitor.visit_assignment(left, right),
+            Statement::CompoundAssignment(left, operator, right, id) => visitor.visit_compound_assignment(left, operator, right, *id),
             Statement::If(condition, block, else_block, scope_id) => visitor.visit_if(condition, block, else_block, *scope_id),
             Statement::Loop(block, scope_id) => visitor.visit_loop(block, *scope_id),
-            Statement::For(id, exp, block, scope_id) => visitor.visit_for(id, exp, block, *scope_id),
+            Statement::For(pattern, exp, block, scope_id) => visitor.visit_for(pattern, exp, block, *scope_id),
             Statement::FnDeclaration(id, params, block, scope_id) => visitor.visit_fn_declaration(id, params, block, *scope_id),
             Statement::Return(exp) => visitor.visit_return(exp),
+            Statement::Break => visitor.visit_break(),
         }
     }
 
@@ -134,21 +332,66 @@ impl Visitable for  Statement{
 
 
 
+// What a `let (..) = value;` or a `for` loop binds the value to, tuples are unpacked
+// element by element and `_` throws its element away
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    Binding(String, SymbolId),
+    Wildcard,
+    Tuple(Vec<Pattern>),
+}
+
+impl Pattern {
+    // The names the pattern declares, left to right
+    pub fn bindings(&self) -> Vec<(&str, SymbolId)> {
+        match self {
+            Pattern::Binding(name, id) => vec![(name.as_str(), *id)],
+            Pattern::Wildcard => Vec::new(),
+            Pattern::Tuple(patterns) => patterns.iter().flat_map(|pattern| pattern.bindings()).collect(),
+        }
+    }
+}
+
+// Patterns as they're written in Ruston, used in diagnostics
+impl std::fmt::Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pattern::Binding(name, _) => write!(f, "{}", name),
+            Pattern::Wildcard => write!(f, "_"),
+            Pattern::Tuple(patterns) if patterns.len() == 1 => write!(f, "({},)", patterns[0]),
+            Pattern::Tuple(patterns) => {
+                let patterns = patterns.iter().map(|pattern| pattern.to_string()).collect::<Vec<_>>();
+                write!(f, "({})", patterns.join(", "))
+            }
+        }
+    }
+}
+
+// An `#[allow(lint, ...)]` attribute, the lints are off in the lines of the statement it's on
+#[derive(Debug, Clone)]
+pub struct Allow {
+    pub lints: Vec<String>,
+    pub lines: RangeInclusive<usize>,
+}
 
 pub struct Sintax<'a> {
     lexer: Lexer<'a>,
     pub program: Vec<Statement>,
     pub table: SymbolTable,
-    current_scope_id: u32,
+    nodes: NodeId, // Number of expressions created so far
+    pub lines: Vec<usize>, // Line of every expression, indexed by node id
+    pub allows: Vec<Allow>,
 }
 
 impl<'a> Sintax<'a> {
-    pub fn new(lexer: Lexer) -> Sintax {
+    pub fn new(lexer: Lexer<'a>) -> Sintax<'a> {
         Sintax {
             lexer,
             program: Vec::new(),
             table: SymbolTable::new(),
-            current_scope_id: 0,
+            nodes: 0,
+            lines: Vec::new(),
+            allows: Vec::new(),
         }
     }
 
@@ -173,60 +416,22 @@ impl<'a> Sintax<'a> {
             Token::For => self.parse_for_loop(),
             Token::Function => self.func_declaration(),
             Token::Return => self.parse_return(),
-
-
-
-            // TODO! recivisar mas tarde,erga se me olvido que queria reviasar :(
-            Token::Identifier(_) => {
-                // this is a hack to check all the next tokens without consuming them
-                let current = self.lexer.save_position();
-                self.lexer.get_next_token(); // consume identifier
-                match self.lexer.peek_token() {
-                    Token::Equal => {
-                        self.lexer.restore_position(current);
-                        self.parse_assignment()
-                    }
-                    Token::Dot | Token::LeftBracket => {
-                        self.lexer.restore_position(current);
-                        let expr = self.parse_expresion();
-                        if self.lexer.peek_token() == Token::Equal {
-                            self.lexer.get_next_token(); // consume '='
-                            let value = self.parse_expresion();
-                            if self.lexer.get_next_token() == Token::Semicolon {
-                                return Statement::Assignment(expr, value);
-                            } else {
-                                let (line, col) = self.lexer.get_current_position();
-                                eprintln!("Expected semicolon at line {} col {}", line, col);
-                                exit(1);
-                            }
-                        } else {
-                            let (line, col) = self.lexer.get_current_position();
-                            eprintln!("Expected '=' at line {} col {}", line, col);
-                            exit(1);
-                        }
-                    }
-                    _ => {
-                        self.lexer.restore_position(current);
-                        self.parse_expresion_statement()
-                    }
-                }
-            }
-            _ => {
-                let exp = self.parse_expresion();
-                if self.lexer.get_next_token() == Token::Semicolon {
-                    return Statement::ExpressionStatement(exp);
-                } else {
-                    eprintln!("Expected semicolon");
-                    exit(1);
-                }
+            Token::Break => {
+                self.lexer.get_next_token(); // consume break
+                self.expect(Token::Semicolon, "semicolon");
+                Statement::Break
             }
+            Token::Hash => self.parse_attribute(),
+            // expressions and assignments both start with an expression
+            _ => self.parse_expresion_statement(),
         }
     }
 
 
 
-    fn parse_tuple(&mut self) -> Expresion {
-        let mut elements = Vec::new();
+    // parses the rest of a tuple, after its first element and comma
+    fn parse_tuple(&mut self, first: Expresion) -> Expresion {
+        let mut elements = vec![first];
         while self.lexer.peek_token() != Token::RightParen {
             elements.push(self.parse_expresion());
             match self.lexer.peek_token() {
@@ -245,7 +450,8 @@ impl<'a> Sintax<'a> {
             }
         }
         if self.lexer.get_next_token() == Token::RightParen {
-            return Expresion::Tuple(elements);
+            let id = self.node_id();
+            return Expresion::Tuple(elements, id);
         } else {
             let (line,col) =  self.lexer.get_current_position();
             eprintln!("Expected ')' at line {} col {}", line, col);
@@ -277,7 +483,8 @@ impl<'a> Sintax<'a> {
             }
         }
         if self.lexer.get_next_token() == Token::RightBracket {
-            return Expresion::Array(elements);
+            let id = self.node_id();
+            return Expresion::Array(elements, id);
         } else {
             let (line,col) =  self.lexer.get_current_position();
             eprintln!("Expected ']' at line {} col {}", line, col);
@@ -369,19 +576,22 @@ impl<'a> Sintax<'a> {
             }
 
             let (line,_) = self.lexer.get_current_position();
-            self.table.insert(Symbol::function(
+            let function = self.table.insert(Symbol::function(
                 id.clone(),
                 line,
-                0,
                 UseType::Declaration,
                 return_type,
                 params.clone(),
                 param_types.clone(),
             ));
 
-            let scope_id = self.generate_scope_id();
-            self.table.create_scope(scope_id);
+            let scope_id = self.table.create_scope(ScopeKind::Function(function), self.lexer.get_current_position().0);
             self.table.enter_scope(scope_id);
+            // parameters live in the function scope and always hold a value
+            for (param, data_type) in params.iter().zip(param_types) {
+                let param_id = self.table.insert(Symbol::variable(param.clone(), line, UseType::Declaration, Some(data_type)));
+                self.table.update_var_assigned(param_id);
+            }
             let block = self.parse_block();
             self.table.exit_scope();
             return Statement::FnDeclaration(id, params, block, scope_id);
@@ -395,48 +605,75 @@ impl<'a> Sintax<'a> {
 
     fn parse_for_loop(&mut self) -> Statement {
         self.lexer.get_next_token(); // for
-        
-        if  self.lexer.get_next_token() != Token::LeftParen {
-            let (line,col) =  self.lexer.get_current_position();
-            eprintln!("Expected '(' at line {} col {}", line, col);
-            exit(1);
-        }
+        self.expect(Token::LeftParen, "'('");
 
-        if let Token::Identifier(id) = self.lexer.get_next_token()  {
-            if self.lexer.get_next_token() == Token::In {
-                let exp = self.parse_expresion();
+        let mut pattern = self.parse_pattern();
+        self.expect(Token::In, "'in'");
+        let exp = self.parse_expresion();
+        self.expect(Token::RightParen, "')'");
 
-                if self.lexer.get_next_token() != Token::RightParen {
-                    let (line,col) =  self.lexer.get_current_position();
-                    eprintln!("Expected ')' at line {} col {}", line, col);
-                    exit(1);
-                }
+        let scope_id = self.table.create_scope(ScopeKind::Loop, self.lexer.get_current_position().0);
+        self.table.enter_scope(scope_id);
+        // the loop variables are declared in the loop scope
+        self.declare_pattern(&mut pattern);
+        let block = self.parse_block();
+        self.table.exit_scope();
+        Statement::For(pattern, exp, block, scope_id)
+    }
 
-                let scope_id = self.generate_scope_id();
-                self.table.create_scope(scope_id);
-                self.table.enter_scope(scope_id);
-                let block = self.parse_block();
-                self.table.exit_scope();
-                return Statement::For(id, exp, block, scope_id);
-            }else{
+    // pattern -> '_' | <identifier> | '(' pattern (',' pattern)* ','? ')'
+    // the names get their symbols from declare_pattern, once the scope they live in is known
+    fn parse_pattern(&mut self) -> Pattern {
+        match self.lexer.get_next_token() {
+            Token::Identifier(name) if name == "_" => Pattern::Wildcard,
+            Token::Identifier(name) => Pattern::Binding(name, 0),
+            Token::LeftParen => {
+                let mut patterns = vec![self.parse_pattern()];
+                let mut trailing_comma = false;
+                while self.lexer.peek_token() == Token::Comma {
+                    self.lexer.get_next_token(); // consume ','
+                    trailing_comma = self.lexer.peek_token() == Token::RightParen;
+                    if trailing_comma {
+                        break;
+                    }
+                    patterns.push(self.parse_pattern());
+                }
+                self.expect(Token::RightParen, "')'");
+                // `(a)` is just `a`, a tuple of one element is written `(a,)`
+                if patterns.len() == 1 && !trailing_comma {
+                    patterns.pop().unwrap()
+                } else {
+                    Pattern::Tuple(patterns)
+                }
+            }
+            token => {
                 let (line,col) =  self.lexer.get_current_position();
-                eprintln!("Expected 'in' at line {} col {}", line, col);
+                eprintln!("Expected identifier, '_' or '(' at line {} col {} but found '{}'", line, col, token);
                 exit(1);
             }
-            
-        }else{
-            let (line,col) =  self.lexer.get_current_position();
-            eprintln!("Expected identifier at line {} col {}", line, col);
-            exit(1);
         }
+    }
 
-
+    // declares the names of the pattern in the current scope, they always hold a value
+    fn declare_pattern(&mut self, pattern: &mut Pattern) {
+        let (line, _) = self.lexer.get_current_position();
+        match pattern {
+            Pattern::Binding(name, id) => {
+                *id = self.table.insert(Symbol::variable(name.clone(), line, UseType::Declaration, None));
+                self.table.update_var_assigned(*id);
+            }
+            Pattern::Wildcard => {}
+            Pattern::Tuple(patterns) => {
+                for pattern in patterns {
+                    self.declare_pattern(pattern);
+                }
+            }
+        }
     }
 
     fn  parse_loop(&mut self) -> Statement{
         self.lexer.get_next_token(); // consume loop
-        let scope_id = self.generate_scope_id();
-        self.table.create_scope(scope_id);
+        let scope_id = self.table.create_scope(ScopeKind::Loop, self.lexer.get_current_position().0);
         self.table.enter_scope(scope_id);
         let block =  self.parse_block();
         self.table.exit_scope();
@@ -448,8 +685,7 @@ impl<'a> Sintax<'a> {
         if self.lexer.get_next_token() == Token::LeftParen { // consume (
             let condition = self.parse_expresion();  // consume inner expresion
             if self.lexer.get_next_token() == Token::RightParen { // consume )  
-                let scope_id = self.generate_scope_id();
-                self.table.create_scope(scope_id);
+                let scope_id = self.table.create_scope(ScopeKind::If, self.lexer.get_current_position().0);
                 self.table.enter_scope(scope_id);
                 let block = self.parse_block();
                 self.table.exit_scope();
@@ -458,12 +694,15 @@ impl<'a> Sintax<'a> {
                     if self.lexer.peek_token() == Token::If {
                         Some(Box::new(self.parse_if()))
                     } else {
-                        self.table.enter_scope(scope_id);
+                        // the else branch gets its own scope, separate from the then branch
+                        let else_scope = self.table.create_scope(ScopeKind::Else, self.lexer.get_current_position().0);
+                        self.table.enter_scope(else_scope);
+                        let always = Expresion::Literal(Literal::Boolean(true), self.node_id());
                         let else_block = Some(Box::new(Statement::If(
-                            Expresion::Literal(Literal::Boolean(true)),
+                            always,
                             self.parse_block(),
                             None,
-                            scope_id,
+                            else_scope,
                         )));
                         self.table.exit_scope();
                         else_block
@@ -505,38 +744,32 @@ impl<'a> Sintax<'a> {
 
 
 
-    fn parse_assignment(&mut self) -> Statement {
-        let token = self.lexer.get_next_token();
-        let id = match token{
-            Token::Identifier(id) => id,
-            _ => {
-                println!("wtf bro {}",token);
-                let (line,col) =  self.lexer.get_current_position();
-                eprintln!("Expected identifier at line {} col {}", line, col);
-                exit(1);
-            }
-        };
-        if self.lexer.get_next_token() == Token::Equal {
-            let exp = self.parse_expresion();
-            if self.lexer.get_next_token() == Token::Semicolon {
-                return Statement::Assignment(Expresion::Identifier(id), exp);
-            } else {
-                let (line,col) =  self.lexer.get_current_position();
-                eprintln!("Expected semicolon at line {} col {}", line, col);
-                exit(1);
-            }
-        } else {
-            let (line,col) =  self.lexer.get_current_position();
-            eprintln!("Expected '=' at line {} col {}", line, col);
+    // let (a, (b, _)) = value;
+    fn parse_destructure(&mut self, is_const: bool) -> Statement {
+        let mut pattern = self.parse_pattern();
+        if is_const {
+            let (line, col) = self.lexer.get_current_position();
+            eprintln!("Syntax Error [Line {}, Column {}]: Const declarations can't unpack a tuple, declare each constant with its type.", line, col);
+            exit(1);
+        }
+        if self.lexer.peek_token() != Token::Equal {
+            let (line, col) = self.lexer.get_current_position();
+            eprintln!("Syntax Error [Line {}, Column {}]: The pattern {} must be given a value.", line, col, pattern);
             exit(1);
         }
+        self.lexer.get_next_token(); // consume '='
+        let expresion = self.parse_expresion();
+        self.expect(Token::Semicolon, "semicolon");
+        // declared after the value, so `let (a, b) = (b, a);` uses the outer names
+        self.declare_pattern(&mut pattern);
+        Statement::Destructure(pattern, expresion)
     }
 
-
-
-
     fn parse_declaration(&mut self) -> Statement {
-        let is_const: bool =  if  self.lexer.get_next_token() == Token::Const  { true }  else  {false};
+        let is_const: bool = self.lexer.get_next_token() == Token::Const;
+        if self.lexer.peek_token() == Token::LeftParen {
+            return self.parse_destructure(is_const);
+        }
         let id = self.lexer.get_next_token();
 
         match id {
@@ -549,7 +782,7 @@ impl<'a> Sintax<'a> {
                     None
                 };
 
-                if (is_const && expresion.is_none()) || (is_const && data_type.is_none()) {
+                if is_const && (expresion.is_none() || data_type.is_none()) {
                     let (line, col) = self.lexer.get_current_position();
                     eprintln!(
                         "Syntax Error [Line {}, Column {}]: Const declarations must include both a type annotation and an initial value.",
@@ -560,8 +793,8 @@ impl<'a> Sintax<'a> {
                 
                 let (line,col) =  self.lexer.get_current_position();
                 if self.lexer.get_next_token() == Token::Semicolon {
-                    self.table.insert(Symbol::variable(id.clone(), line, 0, UseType::Declaration, data_type));
-                    return Statement::Declaration(id, expresion);
+                    let symbol = self.table.insert(Symbol::variable(id.clone(), line, UseType::Declaration, data_type));
+                    return Statement::Declaration(id, expresion, symbol);
                 } else {
                     eprintln!("Expected semicolon at line {} col {}", line, col);
                     exit(1);
@@ -579,178 +812,132 @@ impl<'a> Sintax<'a> {
         }
     }
 
-    // EBNF Grammar  using the extended Backus-Naur Form
-    // the grammar have to define the operator precedence
-    // <expresion> ::=  <literal> | <expresion> <operator> <expresion> | <id> {  <acess_or_call>  }
-    // <acess_or_call> ::= "." <id> |  "(" <listarg> ")"
-    // <literal> ::= <number> | <fncall>
-    // <fncall> ::=   <identifier> "("  <arg>?  <listarg> ')'
-    // <listarg>  ::=  [ <arg> { , <arg>} ]
-    // <number> ::= <integer> | <float>
-    // <integer> ::= <digit>+
-    // <float> ::= <digit>+ "." <digit>+
-    // <operator> ::= "+" | "-" | "*" | "/" | "%" | "**"
-
-
     fn parse_expresion_statement(&mut self) -> Statement {
         let exp = self.parse_expresion();
-        if self.lexer.get_next_token() == Token::Semicolon {
-            return Statement::ExpressionStatement(exp);
-        } else {
-            let (line,col) =  self.lexer.get_current_position();
-            eprintln!("Expected semicolon at line {} col {}", line, col);
-            exit(1);
+        match self.lexer.get_next_token() {
+            Token::Semicolon => Statement::ExpressionStatement(exp),
+            assignment @ (Token::Equal | Token::CompoundEqual(_)) => {
+                if !Expresion::is_place(&exp) {
+                    let (line,col) =  self.lexer.get_current_position();
+                    eprintln!("Invalid left-hand side of assignment at line {} col {}", line, col);
+                    exit(1);
+                }
+                let value = self.parse_expresion();
+                if self.lexer.get_next_token() == Token::Semicolon {
+                    match assignment {
+                        Token::CompoundEqual(operator) => {
+                            let id = self.node_id();
+                            Statement::CompoundAssignment(exp, Token::Operator(operator), value, id)
+                        }
+                        _ => Statement::Assignment(exp, value),
+                    }
+                } else {
+                    let (line,col) =  self.lexer.get_current_position();
+                    eprintln!("Expected semicolon at line {} col {}", line, col);
+                    exit(1);
+                }
+            }
+            _ => {
+                let (line,col) =  self.lexer.get_current_position();
+                eprintln!("Expected semicolon at line {} col {}", line, col);
+                exit(1);
+            }
         }
     }
 
 
 
     fn parse_expresion(&mut self) -> Expresion {
-        self.parse_logical_or()
-    }
-
-    fn parse_logical_or(&mut self) -> Expresion {
-        let mut left = self.parse_logical_and();
-        while self.lexer.peek_token() == Token::LogicalOperator("||".to_string()) {
-            let operator = self.lexer.get_next_token();
-            let right = self.parse_logical_and();
-            left = Expresion::Binary(Box::new(left), operator, Box::new(right));
-        }
-        left
+        self.parse_precedence(0)
     }
 
-    fn parse_logical_and(&mut self) -> Expresion {
-        let mut left = self.parse_comparison();
-        while self.lexer.peek_token() == Token::LogicalOperator("&&".to_string()) {
-            let operator = self.lexer.get_next_token();
-            let right = self.parse_comparison();
-            left = Expresion::Binary(Box::new(left), operator, Box::new(right));
-        }
-        left
-    }
-
-    fn parse_comparison(&mut self) -> Expresion {
-        let mut left = self.parse_term();
-        while self.lexer.peek_token() == Token::LogicalOperator("<".to_string())
-            || self.lexer.peek_token() == Token::LogicalOperator(">".to_string())
-            || self.lexer.peek_token() == Token::LogicalOperator("<=".to_string())
-            || self.lexer.peek_token() == Token::LogicalOperator(">=".to_string())
-            || self.lexer.peek_token() == Token::LogicalOperator("==".to_string())
-            || self.lexer.peek_token() == Token::LogicalOperator("!=".to_string())
-        {
-            let operator = self.lexer.get_next_token();
-            let right = self.parse_term();
-            left = Expresion::Binary(Box::new(left), operator, Box::new(right));
-        }
-        left
-    }
-
-
-
-
-    fn parse_term(&mut self) -> Expresion {
-        let mut left = self.parse_factor();
-        while self.lexer.peek_token() == Token::Operator("+".to_string()) || self.lexer.peek_token() == Token::Operator("-".to_string()) {
-            let operator = self.lexer.get_next_token();
-            let right = self.parse_factor();
-            left = Expresion::Binary(Box::new(left), operator, Box::new(right));
-        }
-        left
-    }
-
-    fn parse_factor(&mut self) -> Expresion {
-        let mut left = self.parse_expoperator();
-        while self.lexer.peek_token() == Token::Operator("*".to_string()) || self.lexer.peek_token() == Token::Operator("/".to_string()) || self.lexer.peek_token() == Token::Operator ("%".to_string()) {
-            let operator = self.lexer.get_next_token();
-            let right = self.parse_expoperator();
-            left = Expresion::Binary(Box::new(left), operator, Box::new(right));
-        }
-
-        left
-    }
-
-    // this is for exponentiation and precedence
-    fn parse_expoperator(&mut self) -> Expresion {
+    // Precedence climbing over the OPERATORS table, parses the operators that
+    // bind at least as tight as `min`
+    fn parse_precedence(&mut self, min: u8) -> Expresion {
         let mut left = self.parse_unary();
-        while self.lexer.peek_token() == Token::Operator("**".to_string()) {
+        while let Some((precedence, associativity)) = binary_operator(&self.lexer.peek_token()) {
+            if precedence < min {
+                break;
+            }
             let operator = self.lexer.get_next_token();
-            let right = self.parse_unary();
-            left = Expresion::Binary(Box::new(left), operator, Box::new(right));
+            // a right associative operator takes the same operator again on its right side
+            let next = match associativity {
+                Associativity::Right => precedence,
+                _ => precedence + 1,
+            };
+            left = match operator {
+                Token::As => Expresion::Cast(Box::new(left), self.get_unit_type(), self.node_id()),
+                Token::Range | Token::RangeInclusive => {
+                    let end = self.parse_precedence(next);
+                    Expresion::Range(Box::new(left), Box::new(end), operator == Token::RangeInclusive, self.node_id())
+                }
+                _ => {
+                    let right = self.parse_precedence(next);
+                    self.binary(left, operator.clone(), right)
+                }
+            };
+            if associativity == Associativity::None {
+                let following = self.lexer.peek_token();
+                if binary_operator(&following).is_some_and(|(other, _)| other == precedence) {
+                    let (line, col) = self.lexer.get_current_position();
+                    eprintln!(
+                        "Syntax error at line {} column {}: '{}' can't be chained with '{}', add parentheses",
+                        line, col, following, operator
+                    );
+                    exit(1);
+                }
+            }
         }
         left
     }
 
-
-
     fn parse_unary(&mut self) -> Expresion {
-        if self.lexer.peek_token() == Token::Operator("-".to_string()) || self.lexer.peek_token() == Token::Operator("!".to_string()) {
+        if matches!(self.lexer.peek_token(), Token::Operator(op) if UNARY_OPERATORS.contains(&op.as_str())) {
             let operator = self.lexer.get_next_token();
-            let right = self.parse_unary(); // Recursively parse unary to handle multiple unary operators
-            return Expresion::Unary(operator, Box::new(right));
+            // the operand takes every operator that binds tighter than the unary ones, that is `**`
+            let right = self.parse_precedence(UNARY_PRECEDENCE);
+            // negative numbers are folded back into a single literal
+            if operator == Token::Operator("-".to_string()) {
+                if let Some(literal) = Expresion::negate_literal(&right) {
+                    return literal;
+                }
+            }
+            let id = self.node_id();
+            return Expresion::Unary(operator, Box::new(right), id);
         }
-        self.parse_literal()
+        let primary = self.parse_literal();
+        self.parse_index_arr_tupla(primary)
     }
 
     fn parse_literal(&mut self) -> Expresion {
         let token = self.lexer.get_next_token();
 
-        match token {
-            Token::Number(start) => {
-                if self.lexer.peek_token() == Token::Range || self.lexer.peek_token() == Token::RangeInclusive {
-                    let inclusive = self.lexer.get_next_token() == Token::RangeInclusive;
-                    let end = self.parse_expresion();
-                    return Expresion::Range(Box::new(Expresion::Literal(Literal::Number(start))), Box::new(end), inclusive);
-                }
-
-                return Expresion::Literal(Literal::Number(start));
-            }
-            _ => {
-                if let Some(literal) = Expresion::get_literal(token.clone()) {
-                    return Expresion::Literal(literal);
-                }
-            }
+        if let Some(literal) = Expresion::get_literal(token.clone()) {
+            return Expresion::Literal(literal, self.node_id());
         }
 
         match token {
-            Token::Identifier(id) => match self.lexer.peek_token() {
-                Token::LeftParen => {
+            Token::Identifier(id) => {
+                if self.lexer.peek_token() == Token::LeftParen {
                     return self.parse_fncall(id);
                 }
-                Token::Dot | Token::LeftBracket => {
-                    let mut expr = Expresion::Identifier(id);
-                    expr = self.parse_index_arr_tupla(expr);
-                    return expr;
-                }
-                _ => {
-                    return Expresion::Identifier(id.to_owned());
-                }
-            },
+                let reference = self.reference(&id);
+                Expresion::Identifier(id, reference, self.node_id())
+            }
             Token::LeftBracket => {
-                return self.parse_array();
+                self.parse_array()
             }
             Token::LeftParen => {
-
-                let state = self.lexer.save_position();
-                let mut is_tuple = false;
-
-                while self.lexer.peek_token() != Token::RightParen {
-                    if self.lexer.peek_token() == Token::Comma {
-                        is_tuple = true;
-                        break;
-                    }
-                    self.lexer.get_next_token();
+                if self.lexer.peek_token() == Token::RightParen {
+                    self.lexer.get_next_token(); // consume )
+                    return Expresion::Tuple(Vec::new(), self.node_id());
                 }
-
-                self.lexer.restore_position(state);
-
-
-                if is_tuple {
-                    return self.parse_tuple();
-                } else {
-                    let exp =  self.parse_expresion();
-                    if self.lexer.get_next_token() == Token::RightParen {
-                        return exp;
-                    } else {
+                // a comma after the first element makes it a tuple, `(a,)` included
+                let exp = self.parse_expresion();
+                match self.lexer.get_next_token() {
+                    Token::Comma => self.parse_tuple(exp),
+                    Token::RightParen => exp,
+                    _ => {
                         let (line,col) =  self.lexer.get_current_position();
                         eprintln!("Expected ')' at line {} col {}", line, col);
                         exit(1);
@@ -769,6 +956,7 @@ impl<'a> Sintax<'a> {
     }
 
     fn parse_fncall(&mut self, name: String) -> Expresion {
+        let reference = self.reference(&name);
         self.lexer.get_next_token(); // consume (
         let mut args: Vec<Expresion> = Vec::new();
 
@@ -794,7 +982,7 @@ impl<'a> Sintax<'a> {
             }
         }
 
-        return Expresion::FnCall(name, args);
+        return Expresion::FnCall(name, args, reference, self.node_id());
     }
 
 
@@ -821,17 +1009,8 @@ impl<'a> Sintax<'a> {
     fn parse_return_type(&mut self) ->  Option<DataType> {
         if  self.lexer.peek_token() == Token::ArrowType {
             self.lexer.get_next_token(); // consume '->'
-            match self.lexer.get_next_token() {
-                Token::TypeInt => Some(DataType::Integer),
-                Token::TypeFloat => Some(DataType::Float),
-                Token::TypeString => Some(DataType::String),
-                Token::TypeBool => Some(DataType::Boolean),
-                _ => {
-                    let (line,col) =  self.lexer.get_current_position();
-                    eprintln!("Expected data type at line {} col {}", line, col);
-                    exit(1);
-                }
-            }
+            // functions can return arrays and tuples too
+            Some(self.get_unit_type())
         } else {
             None
         }
@@ -844,6 +1023,9 @@ impl<'a> Sintax<'a> {
             Token::TypeFloat => DataType::Float,
             Token::TypeString => DataType::String,
             Token::TypeBool => DataType::Boolean,
+            Token::TypeChar => DataType::Char,
+            // fixed width types aren't keywords, like in rust
+            Token::Identifier(name) if IntType::from_name(&name).is_some() => DataType::Sized(IntType::from_name(&name).unwrap()),
             Token::LeftBracket => {
                 let  data_type = self.get_unit_type();
                 if self.lexer.get_next_token() == Token::Semicolon{
@@ -852,7 +1034,7 @@ impl<'a> Sintax<'a> {
 
                         if self.lexer.get_next_token() == Token::RightBracket{
 
-                            return DataType::Array(Box::new(data_type),n);
+                            return DataType::Array(Box::new(data_type),n as i64);
                         }else{
                             let (line,col) =  self.lexer.get_current_position();
                             eprintln!("Expected '[' at line {} col {}", line, col);
@@ -910,9 +1092,75 @@ impl<'a> Sintax<'a> {
 
 
 
-    fn generate_scope_id(&mut self) -> u32 {
-        self.current_scope_id += 1;
-        self.current_scope_id
+    fn node_id(&mut self) -> NodeId {
+        let id = self.nodes;
+        self.nodes += 1;
+        self.lines.push(self.lexer.get_current_position().0);
+        id
+    }
+
+    // `#[allow(lint, ...)]` followed by the statement it applies to
+    fn parse_attribute(&mut self) -> Statement {
+        self.lexer.get_next_token(); // consume #
+        let (start, _) = self.lexer.get_current_position();
+        self.expect(Token::LeftBracket, "'['");
+        match self.lexer.get_next_token() {
+            Token::Identifier(name) if name == "allow" => {}
+            token => {
+                let (line,col) =  self.lexer.get_current_position();
+                eprintln!("Unknown attribute '{}' at line {} col {}, only allow is supported", token, line, col);
+                exit(1);
+            }
+        }
+        self.expect(Token::LeftParen, "'('");
+        // the list may be empty and end with a comma, like rust's
+        let mut lints = Vec::new();
+        loop {
+            match self.lexer.get_next_token() {
+                Token::RightParen => break,
+                Token::Identifier(lint) => lints.push(lint),
+                token => {
+                    let (line,col) =  self.lexer.get_current_position();
+                    eprintln!("Expected a lint name or ')' at line {} col {}, found '{}'", line, col, token);
+                    exit(1);
+                }
+            }
+            match self.lexer.get_next_token() {
+                Token::Comma => {}
+                Token::RightParen => break,
+                token => {
+                    let (line,col) =  self.lexer.get_current_position();
+                    eprintln!("Expected ',' or ')' after a lint name at line {} col {}, found '{}'", line, col, token);
+                    exit(1);
+                }
+            }
+        }
+        self.expect(Token::RightBracket, "']'");
+
+        let statement = self.parse_statement();
+        let end = self.lexer.token_line();
+        self.allows.push(Allow { lints, lines: start..=end });
+        statement
+    }
+
+    fn expect(&mut self, token: Token, name: &str) {
+        if self.lexer.get_next_token() != token {
+            let (line,col) =  self.lexer.get_current_position();
+            eprintln!("Expected {} at line {} col {}", name, line, col);
+            exit(1);
+        }
+    }
+
+    // builds a binary expression with a fresh node id
+    fn binary(&mut self, left: Expresion, operator: Token, right: Expresion) -> Expresion {
+        let id = self.node_id();
+        Expresion::Binary(Box::new(left), operator, Box::new(right), id)
+    }
+
+    // records a use of a name, the resolver binds it to its declaration later
+    fn reference(&mut self, name: &str) -> SymbolId {
+        let (line, _) = self.lexer.get_current_position();
+        self.table.insert(Symbol::reference(name.to_string(), line))
     }
 
     // this have to allow to parse a[0].1 or a.1[0]
@@ -923,25 +1171,23 @@ impl<'a> Sintax<'a> {
                     self.lexer.get_next_token(); // consume '.'
                     match self.lexer.get_next_token() {
                         Token::Number(Number::Integer(index)) => {
-                            expr = Expresion::TupleIndex(Box::new(expr), index as usize);
+                            expr = Expresion::TupleIndex(Box::new(expr), index as usize, self.node_id());
                         }
                         Token::Identifier(member) => {
-                            expr = Expresion::Member(Box::new(expr), member);
+                            expr = Expresion::Member(Box::new(expr), member, self.node_id());
                         },
                         // the number can be float you have to split an create two tupla index
-                        Token::Number(Number::Float(indexes)) =>{
-                            let numbers = indexes.to_string();
-                            let parts: Vec<&str> = numbers.split('.').collect();
-                            if parts.len() < 2 {
+                        // only plain digits on both sides, `t.1.`, `t.1.0e0` and `t.1.0_0` aren't indexes
+                        Token::Number(Number::Float(_, numbers)) =>{
+                            let index = |part: &str| part.bytes().all(|byte| byte.is_ascii_digit()).then(|| part.parse::<usize>().ok()).flatten();
+                            let indexes = numbers.split_once('.').and_then(|(first, second)| Some((index(first)?, index(second)?)));
+                            let Some((index1, index2)) = indexes else {
                                 let (line,col) = self.lexer.get_current_position();
-                                eprintln!("Sintax Error in Line {} and Col {}",line,col);
+                                eprintln!("Sintax Error in Line {} and Col {}: '{}' is not a tuple index",line,col,numbers);
                                 exit(1);
-                            }
-                            let index1: usize = parts[0].parse().unwrap();
-                            let index2: usize = parts[1].parse().unwrap();
-                            expr = Expresion::TupleIndex(Box::new(Expresion::TupleIndex(Box::new(expr), index1)), index2);
-
-
+                            };
+                            let inner = Expresion::TupleIndex(Box::new(expr), index1, self.node_id());
+                            expr = Expresion::TupleIndex(Box::new(inner), index2, self.node_id());
                         },
                         _ => {
                             let (line, col) = self.lexer.get_current_position();
@@ -954,7 +1200,7 @@ impl<'a> Sintax<'a> {
                     self.lexer.get_next_token(); // consume '['
                     let index = self.parse_expresion();
                     if self.lexer.get_next_token() == Token::RightBracket {
-                        expr = Expresion::Index(Box::new(expr), Box::new(index));
+                        expr = Expresion::Index(Box::new(expr), Box::new(index), self.node_id());
                     } else {
                         let (line, col) = self.lexer.get_current_position();
                         eprintln!("Expected ']' at line {} col {}", line, col);
@@ -1,8 +1,9 @@
-use std::collections::HashMap;
-
 use crate::sintax::DataType;
 use std::fmt;
 
+// Every symbol in the table (declaration or reference) gets a unique id
+pub type SymbolId = u32;
+
 #[derive(Debug, Clone)]
 pub enum UseType {
     Declaration,
@@ -20,6 +21,10 @@ pub enum SymbolKind {
         parameters: Vec<String>,
         param_types: Vec<DataType>,
     },
+    // A use of a name, the resolver links it to the declaration it refers to
+    Reference {
+        declaration: Option<SymbolId>,
+    },
 }
 
 impl fmt::Display for SymbolKind {
@@ -33,13 +38,19 @@ impl fmt::Display for SymbolKind {
                     None =>{
                         write!(f,"no defined")
                     }
-            
+
 
                 }
             }
             SymbolKind::Function { data_type, parameters, param_types} => {
                 write!(f, "Function: {:?} with parameters {:?}", data_type, parameters.iter().zip(param_types.iter()).collect::<Vec<_>>())
             }
+            SymbolKind::Reference { declaration } => {
+                match declaration {
+                    Some(id) => write!(f, "Refers to #{}", id),
+                    None => write!(f, "unresolved"),
+                }
+            }
         }
     }
 }
@@ -48,6 +59,7 @@ impl fmt::Display for SymbolKind {
 
 #[derive(Debug, Clone)]
 pub struct Symbol {
+    pub id: SymbolId, // Unique id, assigned by the table on insertion
     pub value: String, // Lexeme
     pub  occurrence: usize, // Line of first occurrence
     pub scope: u32,  // Scope level
@@ -56,122 +68,198 @@ pub struct Symbol {
 }
 
 impl Symbol {
-    pub fn variable(value: String, occurrence: usize, scope: u32, use_type: UseType, data_type: Option<DataType>) -> Self {
+    pub fn variable(value: String, occurrence: usize, use_type: UseType, data_type: Option<DataType>) -> Self {
         Symbol {
+            id: 0,
             value,
             occurrence,
-            scope,
+            scope: 0,
             use_type,
             kind: SymbolKind::Variable { data_type, assigned: false },
         }
     }
 
-    pub fn function(value: String, occurrence: usize, scope: u32, use_type: UseType, data_type: Option<DataType>, parameters: Vec<String>, param_types: Vec<DataType>) -> Self {
+    pub fn function(value: String, occurrence: usize, use_type: UseType, data_type: Option<DataType>, parameters: Vec<String>, param_types: Vec<DataType>) -> Self {
         Symbol {
+            id: 0,
             value,
             occurrence,
-            scope,
+            scope: 0,
             use_type,
             kind: SymbolKind::Function { data_type, parameters, param_types },
         }
     }
+
+    pub fn reference(value: String, occurrence: usize) -> Self {
+        Symbol {
+            id: 0,
+            value,
+            occurrence,
+            scope: 0,
+            use_type: UseType::Reference,
+            kind: SymbolKind::Reference { declaration: None },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScopeKind {
+    Block, // the top level of the program
+    Function(SymbolId), // body of the function declared by the symbol
+    Loop, // both `loop` and `for`
+    If,
+    Else,
+}
+
+#[derive(Debug, Clone)]
+pub struct Scope {
+    pub id: u32,
+    pub parent: Option<u32>, // None only for the global scope
+    pub kind: ScopeKind,
+    pub line: usize, // Line the statement that opens the scope is at
+    pub children: Vec<u32>, // Nested scopes, in source order
+    pub declarations: Vec<SymbolId>, // Declarations made directly in this scope, in source order
 }
 
 #[derive(Debug, Clone)]
 pub struct SymbolTable {
-    pub all_scopes: HashMap<u32, HashMap<String, Symbol>>, // Cambiar a HashMap con ID de scope
-    active_scopes: Vec<u32>, // Pila de IDs de scopes activos
+    pub symbols: Vec<Symbol>, // Every symbol, indexed by its id
+    pub scopes: Vec<Scope>, // Every scope, indexed by its id. The global scope is 0
+    current_scope: u32, // Scope the parser is currently in
 }
 
 impl SymbolTable {
     pub fn new() -> Self {
-
-        let mut table = SymbolTable {
-            all_scopes: HashMap::new(),
-            active_scopes: vec![0],
-        };
-
-        table.create_scope(0); // Crear scope global
-        table
+        SymbolTable {
+            symbols: Vec::new(),
+            scopes: vec![Scope {
+                id: 0,
+                parent: None,
+                kind: ScopeKind::Block,
+                line: 1,
+                children: Vec::new(),
+                declarations: Vec::new(),
+            }],
+            current_scope: 0,
+        }
     }
 
-    pub fn create_scope(&mut self, scope_id: u32) {
-        self.all_scopes.insert(scope_id, HashMap::new()); // Crear nuevo scope con ID
+    // Creates a scope nested in the current one and returns its id
+    pub fn create_scope(&mut self, kind: ScopeKind, line: usize) -> u32 {
+        let id = self.scopes.len() as u32;
+        self.scopes.push(Scope {
+            id,
+            parent: Some(self.current_scope),
+            kind,
+            line,
+            children: Vec::new(),
+            declarations: Vec::new(),
+        });
+        self.scopes[self.current_scope as usize].children.push(id);
+        id
     }
 
     pub fn enter_scope(&mut self, scope_id: u32) {
-        if self.all_scopes.contains_key(&scope_id) {
-            self.active_scopes.push(scope_id);
+        if (scope_id as usize) < self.scopes.len() {
+            self.current_scope = scope_id;
         }
     }
 
     pub fn exit_scope(&mut self) {
-        self.active_scopes.pop();
+        if let Some(parent) = self.scope(self.current_scope).parent {
+            self.current_scope = parent;
+        }
     }
 
-    pub fn insert(&mut self, symbol: Symbol) {
-        if let Some(&current_scope) = self.active_scopes.last() {
-            if let Some(scope) = self.all_scopes.get_mut(&current_scope) {
-                scope.insert(symbol.value.clone(), symbol);
-            }
+    pub fn scope(&self, scope_id: u32) -> &Scope {
+        &self.scopes[scope_id as usize]
+    }
+
+    pub fn children(&self, scope_id: u32) -> &[u32] {
+        &self.scope(scope_id).children
+    }
+
+    // Function declared with this scope as its body, if any
+    pub fn function(&self, scope_id: u32) -> Option<SymbolId> {
+        match self.scope(scope_id).kind {
+            ScopeKind::Function(function) => Some(function),
+            _ => None,
         }
     }
 
-    pub fn lookup(&self, value: &str) -> Option<&Symbol> {
-        for &scope_id in self.active_scopes.iter().rev() {
-            if let Some(scope) = self.all_scopes.get(&scope_id) {
-                if let Some(symbol) = scope.get(value) {
-                    return Some(symbol);
-                }
-            }
+    // Enclosing scopes of `scope_id`, from its parent up to the global scope
+    pub fn ancestors(&self, scope_id: u32) -> impl Iterator<Item = u32> + '_ {
+        std::iter::successors(self.scope(scope_id).parent, move |&id| self.scope(id).parent)
+    }
+
+    // Adds the symbol to the current scope and returns the id given to it
+    pub fn insert(&mut self, mut symbol: Symbol) -> SymbolId {
+        let id = self.symbols.len() as SymbolId;
+        symbol.id = id;
+        symbol.scope = self.current_scope;
+        if let UseType::Declaration = symbol.use_type {
+            self.scopes[self.current_scope as usize].declarations.push(id);
         }
-        None
+        self.symbols.push(symbol);
+        id
+    }
+
+    // Latest declaration of `value` visible from the current scope
+    pub fn lookup(&self, value: &str) -> Option<&Symbol> {
+        std::iter::once(self.current_scope)
+            .chain(self.ancestors(self.current_scope))
+            .find_map(|scope_id| {
+                self.scope(scope_id).declarations.iter().rev().find(|&&id| self.symbol(id).value == value)
+            })
+            .map(|&id| self.symbol(id))
+    }
+
+    // First declaration of `value` made directly in the given scope
+    pub fn declaration_in(&self, scope_id: u32, value: &str) -> Option<SymbolId> {
+        self.scope(scope_id)
+            .declarations
+            .iter()
+            .find(|&&id| self.symbol(id).value == value)
+            .copied()
     }
 
     pub fn get_all_symbols(&self) -> Vec<&Symbol> {
-        self.all_scopes.values().flat_map(|scope| scope.values()).collect()
+        self.symbols.iter().collect()
     }
 
-    pub fn get_symbol(&mut self, value: &str) -> Option<&mut Symbol> {
-        for scope in self.all_scopes.values_mut() {
-            if let Some(symbol) = scope.get_mut(value) {
-                return Some(symbol);
-            }
-        }
-        None
+    pub fn symbol(&self, id: SymbolId) -> &Symbol {
+        &self.symbols[id as usize]
     }
 
-    pub fn read_symbol(&self, value: &str) -> Option<&Symbol> {
-        for scope in self.all_scopes.values() {
-            if let Some(symbol) = scope.get(value) {
-                return Some(symbol);
-            }
-        }
-        None
+    pub fn symbol_mut(&mut self, id: SymbolId) -> &mut Symbol {
+        &mut self.symbols[id as usize]
     }
 
-    pub fn update_var_type(&mut self, value: &str, value_type: DataType) {
-        if let Some(symbol) = self.get_symbol(value) {
-            symbol.kind = SymbolKind::Variable { data_type: Some(value_type), assigned: false };
+    // Links a reference to the declaration it refers to
+    pub fn bind(&mut self, reference: SymbolId, declaration: SymbolId) {
+        self.symbol_mut(reference).kind = SymbolKind::Reference { declaration: Some(declaration) };
+    }
+
+    // Returns the declaration a symbol stands for: itself if it's a declaration,
+    // the bound declaration if it's a resolved reference
+    pub fn resolve(&self, id: SymbolId) -> Option<SymbolId> {
+        match &self.symbol(id).kind {
+            SymbolKind::Reference { declaration } => *declaration,
+            _ => Some(id),
         }
     }
 
-    pub fn update_var_assigned(&mut self, value: &str) {
-        if let Some(symbol) = self.get_symbol(value) {
-            if let SymbolKind::Variable { data_type, .. } = &symbol.kind {
-                symbol.kind = SymbolKind::Variable { data_type: data_type.clone(), assigned: true };
-            }
+    pub fn update_var_type(&mut self, id: SymbolId, value_type: DataType) {
+        let symbol = self.symbol_mut(id);
+        if let SymbolKind::Variable { .. } = symbol.kind {
+            symbol.kind = SymbolKind::Variable { data_type: Some(value_type), assigned: false };
         }
     }
 
-    pub fn get_params(&self, name: &str) -> Option<&Vec<DataType>> {
-        // This functions returns a vector with the parameters of a function
-        // in a tuple with the name of the parameter and its data type
-        if let Some(symbol) = self.read_symbol(name) {
-            if let SymbolKind::Function { param_types, .. } = &symbol.kind {
-                return Some(param_types);
-            }
+    pub fn update_var_assigned(&mut self, id: SymbolId) {
+        let symbol = self.symbol_mut(id);
+        if let SymbolKind::Variable { data_type, .. } = &symbol.kind {
+            symbol.kind = SymbolKind::Variable { data_type: data_type.clone(), assigned: true };
         }
-        None
-    } 
+    }
 }
@@ -1,212 +1,245 @@
-use crate::sintax::{Statement, Expresion, Literal};
+use std::collections::HashMap;
 
-pub fn display_tree(program: &Vec<Statement>) {
+use crate::sintax::{Statement, Expresion, Literal, DataType, NodeId};
+
+// Prints the program as a tree, expressions show their type when `types` has it
+pub fn display_tree(program: &Vec<Statement>, types: &HashMap<NodeId, DataType>) {
   println!("Program:");
   for statement in program {
-    display_statement(statement, 0, true);
+    display_statement(statement, types, 0, true);
   }
 }
 
-fn display_statement(statement: &Statement, indent: usize, is_last: bool) {
+fn display_statement(statement: &Statement, types: &HashMap<NodeId, DataType>, indent: usize, is_last: bool) {
   let indent_str = " ".repeat(indent);
   let branch = if is_last { "└── " } else { "├── " };
   match statement {
     Statement::ExpressionStatement(expr) => {
       println!("{}{}ExpressionStatement:", indent_str, branch);
-      display_expression(expr, indent + 4, true);
+      display_expression(expr, types, indent + 4, true);
     }
-    Statement::Declaration(name, expr) => {
+    Statement::Declaration(name, expr, _) => {
       println!("{}{}Declaration: {}", indent_str, branch, name);
       if let Some(expr) = expr {
-        display_expression(expr, indent + 4, true);
+        display_expression(expr, types, indent + 4, true);
       }
     }
+    Statement::Destructure(pattern, expr) => {
+      println!("{}{}Destructure: {}", indent_str, branch, pattern);
+      display_expression(expr, types, indent + 4, true);
+    }
     Statement::Assignment(lhs, rhs) => {
       println!("{}{}Assignment:", indent_str, branch);
-      display_expression(lhs, indent + 4, false);
-      display_expression(rhs, indent + 4, true);
+      display_expression(lhs, types, indent + 4, false);
+      display_expression(rhs, types, indent + 4, true);
+    }
+    Statement::CompoundAssignment(lhs, op, rhs, _) => {
+      println!("{}{}Compound Assignment:", indent_str, branch);
+      display_expression(lhs, types, indent + 4, false);
+      println!("{}    Operator: {:?}", indent_str, op);
+      display_expression(rhs, types, indent + 4, true);
     }
     Statement::If(cond, body, else_stmt,_) => {
       println!("{}{}If:", indent_str, branch);
-      display_expression(cond, indent + 4, false);
+      display_expression(cond, types, indent + 4, false);
       println!("{}    Body:", indent_str);
       for (i, stmt) in body.iter().enumerate() {
-        display_statement(stmt, indent + 8, i == body.len() - 1);
+        display_statement(stmt, types, indent + 8, i == body.len() - 1);
       }
       if let Some(else_stmt) = else_stmt {
         println!("{}    Else:", indent_str);
-        display_statement(else_stmt, indent + 4, true);
+        display_statement(else_stmt, types, indent + 4, true);
       }
     }
     Statement::Loop(body,_) => {
       println!("{}{}Loop:", indent_str, branch);
       for (i, stmt) in body.iter().enumerate() {
-        display_statement(stmt, indent + 4, i == body.len() - 1);
+        display_statement(stmt, types, indent + 4, i == body.len() - 1);
       }
     }
     Statement::For(var, range, body, _) => {
       println!("{}{}For: {}", indent_str, branch, var);
-      display_expression(range, indent + 4, false);
+      display_expression(range, types, indent + 4, false);
       for (i, stmt) in body.iter().enumerate() {
-        display_statement(stmt, indent + 8, i == body.len() - 1);
+        display_statement(stmt, types, indent + 8, i == body.len() - 1);
       }
     }
     Statement::FnDeclaration(name, params, body, _) => {
       println!("{}{}Function Declaration: {}", indent_str, branch, name);
       println!("{}    Parameters: {:?}", indent_str, params);
       for (i, stmt) in body.iter().enumerate() {
-        display_statement(stmt, indent + 8, i == body.len() - 1);
+        display_statement(stmt, types, indent + 8, i == body.len() - 1);
       }
     }
     Statement::Return(expr) => {
       println!("{}{}Return:", indent_str, branch);
       if let Some(expr) = expr {
-        display_expression(expr, indent + 4, true);
+        display_expression(expr, types, indent + 4, true);
       }
     }
+    Statement::Break => {
+      println!("{}{}Break", indent_str, branch);
+    }
   }
 }
 
-fn display_expression(expr: &Expresion, indent: usize, is_last: bool) {
+fn display_expression(expr: &Expresion, types: &HashMap<NodeId, DataType>, indent: usize, is_last: bool) {
   let indent_str = " ".repeat(indent);
   let branch = if is_last { "└── " } else { "├── " };
+  let branch = match types.get(&expr.id()) {
+    Some(data_type) => format!("{}<{}> ", branch, data_type),
+    None => branch.to_string(),
+  };
   match expr {
-    Expresion::Literal(lit) => {
+    Expresion::Literal(lit, _) => {
       println!("{}{}Literal: {:?}", indent_str, branch, lit);
     }
-    Expresion::Identifier(name) => {
+    Expresion::Identifier(name, _, _) => {
       println!("{}{}Identifier: {}", indent_str, branch, name);
     }
-    Expresion::Binary(lhs, op, rhs) => {
+    Expresion::Binary(lhs, op, rhs, _) => {
       println!("{}{}Binary Expression:", indent_str, branch);
-      display_expression(lhs, indent + 4, false);
+      display_expression(lhs, types, indent + 4, false);
       println!("{}    Operator: {:?}", indent_str, op);
-      display_expression(rhs, indent + 4, true);
+      display_expression(rhs, types, indent + 4, true);
     }
-    Expresion::FnCall(name, args) => {
+    Expresion::FnCall(name, args, _, _) => {
       println!("{}{}Function Call: {}", indent_str, branch, name);
       for (i, arg) in args.iter().enumerate() {
-        display_expression(arg, indent + 4, i == args.len() - 1);
+        display_expression(arg, types, indent + 4, i == args.len() - 1);
       }
     }
-    Expresion::Tuple(elements) => {
+    Expresion::Tuple(elements, _) => {
       println!("{}{}Tuple:", indent_str, branch);
       for (i, element) in elements.iter().enumerate() {
-        display_expression(element, indent + 4, i == elements.len() - 1);
+        display_expression(element, types, indent + 4, i == elements.len() - 1);
       }
     }
-    Expresion::Array(elements) => {
+    Expresion::Array(elements, _) => {
       println!("{}{}Array:", indent_str, branch);
       for (i, element) in elements.iter().enumerate() {
-        display_expression(element, indent + 4, i == elements.len() - 1);
+        display_expression(element, types, indent + 4, i == elements.len() - 1);
       }
     }
-    Expresion::Index(array, index) => {
+    Expresion::Index(array, index, _) => {
       println!("{}{}Index:", indent_str, branch);
-      display_expression(array, indent + 4, false);
-      display_expression(index, indent + 4, true);
+      display_expression(array, types, indent + 4, false);
+      display_expression(index, types, indent + 4, true);
     }
-    Expresion::Member(expr, member) => {
+    Expresion::Member(expr, member, _) => {
       println!("{}{}Member Access: {}", indent_str, branch, member);
-      display_expression(expr, indent + 4, true);
+      display_expression(expr, types, indent + 4, true);
     }
-    Expresion::TupleIndex(expr, index) => {
+    Expresion::TupleIndex(expr, index, _) => {
       println!("{}{}Tuple Index: {}", indent_str, branch, index);
-      display_expression(expr, indent + 4, true);
+      display_expression(expr, types, indent + 4, true);
     }
-    Expresion::Unary(op, expr) => {
+    Expresion::Unary(op, expr, _) => {
       println!("{}{}Unary Expression:", indent_str, branch);
       println!("{}    Operator: {:?}", indent_str, op);
-      display_expression(expr, indent + 4, true);
+      display_expression(expr, types, indent + 4, true);
     }
-    Expresion::Range(start, end, inclusive) => {
+    Expresion::Range(start, end, inclusive, _) => {
       println!("{}{}Range:", indent_str, branch);
-      display_expression(start, indent + 4, false);
-      display_expression(end, indent + 4, false);
+      display_expression(start, types, indent + 4, false);
+      display_expression(end, types, indent + 4, false);
       println!("{}    Inclusive: {}", indent_str, inclusive);
     }
+    Expresion::Cast(expr, data_type, _) => {
+      println!("{}{}Cast: {}", indent_str, branch, data_type);
+      display_expression(expr, types, indent + 4, true);
+    }
   }
 }
 
+// Writes the expression to stderr, it's used to point at the faulty code in diagnostics
 pub fn print_expression(expr: &Expresion) {
   match expr {
-    Expresion::Literal(lit) => {
+    Expresion::Literal(lit, _) => {
       match lit {
-        // TODO: The display of number is buggy, it prints all as integers
         Literal::Number(number) => {
-          print!("{:?} ", number);
+          eprint!("{} ", number);
         }
         Literal::Boolean(value) => {
-          print!("{:?} ", value);
+          eprint!("{:?} ", value);
         }
         Literal::String(value) => {
-          print!("{:?} ", value);
+          eprint!("{:?} ", value);
+        }
+        Literal::Char(value) => {
+          eprint!("{:?} ", value);
         }
       }
     }
-    Expresion::Identifier(name) => {
-      print!("{} ", name);
+    Expresion::Identifier(name, _, _) => {
+      eprint!("{} ", name);
     }
-    Expresion::Binary(lhs, op, rhs) => {
+    Expresion::Binary(lhs, op, rhs, _) => {
       print_expression(lhs);
-      print!("{} ", op);
+      eprint!("{} ", op);
       print_expression(rhs);
     }
-    Expresion::FnCall(name, args) => {
-      print!("{} ( ", name);
+    Expresion::FnCall(name, args, _, _) => {
+      eprint!("{} ( ", name);
       for i in 0..args.len() {
         print_expression(&args[i]);
         if i < args.len() - 1 {
-          print!(", ");
+          eprint!(", ");
         }
       }
-      print!(") ");
+      eprint!(") ");
     }
-    Expresion::Tuple(elements) => {
-      print!("(");
+    Expresion::Tuple(elements, _) => {
+      eprint!("(");
       for (i, element) in elements.iter().enumerate() {
         print_expression(element);
         if i < elements.len() - 1 {
-          print!(", ");
+          eprint!(", ");
         }
       }
-      print!(") ");
+      eprint!(") ");
     }
-    Expresion::Array(elements) => {
-      print!("[");
+    Expresion::Array(elements, _) => {
+      eprint!("[");
       for (i, element) in elements.iter().enumerate() {
         print_expression(element);
         if i < elements.len() - 1 {
-          print!(", ");
+          eprint!(", ");
         }
       }
-      print!("] ");
+      eprint!("] ");
     }
-    Expresion::Index(array, index) => {
+    Expresion::Index(array, index, _) => {
       print_expression(array);
+      eprint!("[ ");
       print_expression(index);
+      eprint!("] ");
     }
-    Expresion::Member(expr, member) => {
+    Expresion::Member(expr, member, _) => {
       print_expression(expr);
-      print!(".{} ", member);
+      eprint!(".{} ", member);
     }
-    Expresion::TupleIndex(expr, index) => {
+    Expresion::TupleIndex(expr, index, _) => {
       print_expression(expr);
-      print!("[{}] ", index);
+      eprint!(".{} ", index);
     }
-    Expresion::Unary(op, expr) => {
-      print!("{} ", op);
+    Expresion::Unary(op, expr, _) => {
+      eprint!("{} ", op);
       print_expression(expr);
     }
-    Expresion::Range(start, end, inclusive) => {
+    Expresion::Range(start, end, inclusive, _) => {
       print_expression(start);
       if *inclusive {
-        print!("..=");
+        eprint!("..=");
       } else {
-        print!("..");
+        eprint!("..");
       }
       print_expression(end);
     }
+    Expresion::Cast(expr, data_type, _) => {
+      print_expression(expr);
+      eprint!("as {} ", data_type);
+    }
   }
 }
 
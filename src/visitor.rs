@@ -1,63 +1,74 @@
 use crate::sintax::*;
 use crate::lexer::{Number, Token};
+use crate::table::SymbolId;
 
 // Literal {
 //   Number(Number),
 //   String(String),
 //   Boolean(bool),
+//   Char(char),
 // }
 
 // pub enum Expresion {
-//   Literal(Literal),
-//   Identifier(String),
-//   Binary(Box<Expresion>, Token, Box<Expresion>),
-//   FnCall(String, Vec<Expresion>),
-//   Tuple(Vec<Expresion>),
-//   Array(Vec<Expresion>),
-//   Index(Box<Expresion>, Box<Expresion>),
-//   Member(Box<Expresion>, String),
-//   TupleIndex(Box<Expresion>, usize),
-//   Unary(Token, Box<Expresion>),
-//   Range(Box<Expresion>, Box<Expresion>, bool), // bool indica si es inclusivo
+//   Literal(Literal, NodeId),
+//   Identifier(String, SymbolId, NodeId),
+//   Binary(Box<Expresion>, Token, Box<Expresion>, NodeId),
+//   FnCall(String, Vec<Expresion>, SymbolId, NodeId),
+//   Tuple(Vec<Expresion>, NodeId),
+//   Array(Vec<Expresion>, NodeId),
+//   Index(Box<Expresion>, Box<Expresion>, NodeId),
+//   Member(Box<Expresion>, String, NodeId),
+//   TupleIndex(Box<Expresion>, usize, NodeId),
+//   Unary(Token, Box<Expresion>, NodeId),
+//   Range(Box<Expresion>, Box<Expresion>, bool, NodeId), // bool indica si es inclusivo
+//   Cast(Box<Expresion>, DataType, NodeId),
 // }
 
 // pub enum Statement {
 // ExpressionStatement(Expresion),
-// Declaration(String, Option<Expresion>),
+// Declaration(String, Option<Expresion>, SymbolId),
+// Destructure(Pattern, Expresion),
 // Assignment(Expresion, Expresion),
+// CompoundAssignment(Expresion, Token, Expresion, NodeId),
 // If(Expresion, Vec<Statement>, Option< Box<Statement>>, u32),
 // Loop(Vec<Statement>, u32),
-// For(String,Expresion,Vec<Statement>, u32),
+// For(Pattern,Expresion,Vec<Statement>, u32),
 // FnDeclaration(String, Vec<String>, Vec<Statement>, u32),
 // Return(Option<Expresion>),
+// Break,
 // }
 
 pub trait Visitor {
   fn visit_expression_statement(&mut self, expression: &Expresion) -> String;
-  fn visit_declaration(&mut self, name: &String, value: &Option<Expresion>) -> String;
+  fn visit_declaration(&mut self, name: &str, value: &Option<Expresion>, symbol: SymbolId) -> String;
+  fn visit_destructure(&mut self, pattern: &Pattern, value: &Expresion) -> String;
   fn visit_assignment(&mut self, left: &Expresion, right: &Expresion) -> String;
-  fn visit_if(&mut self, condition: &Expresion, then_branch: &Vec<Statement>, else_branch: &Option<Box<Statement>>, scope_id: u32) -> String;
-  fn visit_loop(&mut self, body: &Vec<Statement>, scope_id: u32) -> String;
-  fn visit_for(&mut self, variable: &String, iterable: &Expresion, body: &Vec<Statement>, scope_id: u32) -> String;
-  fn visit_fn_declaration(&mut self, name: &String, params: &Vec<String>, body: &Vec<Statement>, scope_id: u32) -> String;
+  fn visit_compound_assignment(&mut self, left: &Expresion, operator: &Token, right: &Expresion, id: NodeId) -> String;
+  fn visit_if(&mut self, condition: &Expresion, then_branch: &[Statement], else_branch: &Option<Box<Statement>>, scope_id: u32) -> String;
+  fn visit_loop(&mut self, body: &[Statement], scope_id: u32) -> String;
+  fn visit_for(&mut self, pattern: &Pattern, iterable: &Expresion, body: &[Statement], scope_id: u32) -> String;
+  fn visit_fn_declaration(&mut self, name: &str, params: &[String], body: &[Statement], scope_id: u32) -> String;
   fn visit_return(&mut self, value: &Option<Expresion>) -> String;
+  fn visit_break(&mut self) -> String;
 
   fn visit_literal(&mut self, literal: &Literal) -> String;
-  fn visit_identifier(&mut self, identifier: &String) -> String;
-  fn visit_binary(&mut self, left: &Expresion, operator: &Token, right: &Expresion) -> String;
-  fn visit_fn_call(&mut self, name: &String, args: &Vec<Expresion>) -> String;
-  fn visit_array(&mut self, elements: &Vec<Expresion>) -> String;
-  fn visit_unary(&mut self, operator: &Token, operand: &Expresion) -> String;
+  fn visit_identifier(&mut self, identifier: &str, reference: SymbolId) -> String;
+  fn visit_binary(&mut self, left: &Expresion, operator: &Token, right: &Expresion, id: NodeId) -> String;
+  fn visit_fn_call(&mut self, name: &str, args: &[Expresion], reference: SymbolId) -> String;
+  fn visit_array(&mut self, elements: &[Expresion]) -> String;
+  fn visit_unary(&mut self, operator: &Token, operand: &Expresion, id: NodeId) -> String;
   fn visit_range(&mut self, start: &Expresion, end: &Expresion, inclusive: bool) -> String;
-  fn visit_tuple(&mut self, elements: &Vec<Expresion>) -> String;
+  fn visit_tuple(&mut self, elements: &[Expresion]) -> String;
   fn visit_index(&mut self, array: &Expresion, index: &Expresion) -> String;
-  fn visit_member(&mut self, object: &Expresion, member: &String) -> String;
+  fn visit_member(&mut self, object: &Expresion, member: &str) -> String;
   fn visit_tuple_index(&mut self, tuple: &Expresion, index: usize) -> String;
+  fn visit_cast(&mut self, expr: &Expresion, data_type: &DataType) -> String;
 
 
   fn visit_number(&mut self, number: &Number) -> String;
-  fn visit_string(&mut self, string: &String) -> String;
+  fn visit_string(&mut self, string: &str) -> String;
   fn visit_boolean(&mut self, boolean: &bool) -> String;
+  fn visit_char(&mut self, char: char) -> String;
 }
 
 pub trait Visitable {
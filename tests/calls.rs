@@ -0,0 +1,32 @@
+mod common;
+
+use common::{compile, python};
+
+#[test]
+fn undeclared_functions_come_from_python() {
+    let code = python("calls_undeclared", "let a = [1, 2, 3];\nlet n = len(a) + 1;\nfor (i in 0..len(a)) {\n    print(a[len(a) - 1 - i], n);\n}\n", &[]);
+    assert!(code.contains("n = (len(a) + 1)"));
+    assert!(code.contains("for i in range(0, len(a)):"));
+}
+
+#[test]
+fn arguments_of_undeclared_functions_are_checked() {
+    let compiled = compile("calls_undeclared_arguments", "print(1 + true);\n", &[]);
+    assert!(!compiled.success);
+    assert!(compiled.stderr.contains("Non-numeric types"), "{}", compiled.stderr);
+}
+
+#[test]
+fn dynamic_values_need_a_cast_to_be_bools() {
+    for (name, source) in [
+        ("calls_dynamic_condition", "let a = [1];\nif (len(a)) {\n    print(a);\n}\n"),
+        ("calls_dynamic_and", "let a = [1];\nlet b = len(a) && true;\n"),
+        ("calls_dynamic_not", "let a = [1];\nlet b = !len(a);\n"),
+    ] {
+        let compiled = compile(name, source, &[]);
+        assert!(!compiled.success, "{} compiled", name);
+        assert!(compiled.stderr.contains("dynamic"), "{}", compiled.stderr);
+    }
+    let code = python("calls_dynamic_cast", "let a = [1];\nif (len(a) as bool) {\n    print(a);\n}\n", &[]);
+    assert!(code.contains("if bool(len(a)):"));
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use common::{compile, python};
+
+#[test]
+fn integer_division_and_remainder_use_the_truncating_helpers() {
+    let code = python("codegen_truncate", "let a = 7;\nlet b = -2;\nprint(a / b, a % b);\n", &[]);
+    assert!(code.contains("def _r_div(a, b):"));
+    assert!(code.contains("def _r_rem(a, b):"));
+    assert!(code.contains("print(_r_div(a, b), _r_rem(a, b))"));
+}
+
+#[test]
+fn float_division_and_remainder_stay_python_operators() {
+    let code = python("codegen_float_division", "let a = 7.5;\nprint(a / 2.0, a % 2.0);\n", &[]);
+    assert!(code.contains("(a / 2"));
+    assert!(code.contains("(a % 2"));
+    assert!(!code.contains("_r_"));
+}
+
+#[test]
+fn helpers_are_emitted_only_when_used() {
+    let code = python("codegen_no_helpers", "let a = 7;\nprint(a * 2 - 1);\n", &[]);
+    assert!(!code.contains("_r_"));
+}
+
+#[test]
+fn fixed_width_arithmetic_is_checked_by_default() {
+    let code = python("codegen_overflow_check", "let a: i8 = 100;\nlet b: u8 = 3;\nprint(a + a, b - 5);\n", &[]);
+    assert!(code.contains("def _r_check(value, bits, signed):"));
+    assert!(code.contains("_r_check((a + a), 8, True)"));
+    assert!(code.contains("_r_check((b - 5), 8, False)"));
+    assert!(!code.contains("_r_wrap"));
+}
+
+#[test]
+fn fixed_width_arithmetic_wraps_with_the_flag() {
+    let code = python("codegen_overflow_wrap", "let a: i16 = 100;\nprint(a * a);\n", &["--overflow=wrap"]);
+    assert!(code.contains("def _r_wrap(value, bits, signed):"));
+    assert!(code.contains("_r_wrap((a * a), 16, True)"));
+    assert!(!code.contains("_r_check"));
+}
+
+#[test]
+fn unknown_overflow_mode_is_rejected() {
+    let compiled = compile("codegen_overflow_mode", "let a: i8 = 1;\n", &["--overflow=saturate"]);
+    assert!(!compiled.success);
+    assert!(compiled.stderr.contains("Invalid overflow mode 'saturate'"));
+}
+
+#[test]
+fn division_of_values_from_python_truncates() {
+    let code = python("codegen_dynamic_division", "let a = [1, 2, 3];\nprint(len(a) / 2, -len(a) % 2, len(a) / len(a), len(a) % len(a));\n", &[]);
+    assert!(code.contains("_r_div(len(a), 2)"));
+    assert!(code.contains("_r_rem(-len(a), 2)"));
+    assert!(code.contains("_r_div(len(a), len(a))"));
+    assert!(code.contains("_r_rem(len(a), len(a))"));
+}
+
+#[test]
+fn division_of_values_from_python_by_a_float_stays_python_division() {
+    let code = python("codegen_dynamic_float_division", "let a = [1, 2, 3];\nprint(len(a) / 2.0);\n", &[]);
+    assert!(code.contains("(len(a) / 2.0)"));
+    assert!(!code.contains("_r_"));
+}
+
+#[test]
+fn calls_in_the_indexes_of_a_place_written_twice_run_once() {
+    let source = "fn pick() -> int {\n    return 0;\n}\nlet a = [10, 20];\na[pick()] /= 3;\nprint(a);\n";
+    let code = python("codegen_hoisted_index", source, &[]);
+    assert!(code.contains("_r_index0 = pick()\na[_r_index0] = _r_div(a[_r_index0], 3)"), "{}", code);
+}
+
+#[test]
+fn the_value_runs_before_the_calls_in_the_place() {
+    let source = "fn pick() -> int {\n    return 0;\n}\nfn value() -> int {\n    return 2;\n}\nlet a = [10, 20];\na[pick()] += value();\na[pick()] /= value();\na[1] += value();\nprint(a);\n";
+    let code = python("codegen_hoisted_value", source, &[]);
+    assert!(code.contains("_r_value = value()\na[pick()] += _r_value\n"), "{}", code);
+    assert!(code.contains("_r_value = value()\n_r_index0 = pick()\na[_r_index0] = _r_div(a[_r_index0], _r_value)\n"), "{}", code);
+    // without calls in the place the order doesn't show
+    assert!(code.contains("a[1] += value()\n"), "{}", code);
+}
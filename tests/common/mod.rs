@@ -0,0 +1,42 @@
+// Helpers for the tests that run the ruston binary on a source file
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub struct Compiled {
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+// Where the tests write their source files
+pub fn dir() -> PathBuf {
+    std::env::temp_dir().join("ruston-tests")
+}
+
+// Writes the source to a file named after the test and compiles it, the
+// arguments go before the path
+pub fn compile(name: &str, source: &str, args: &[&str]) -> Compiled {
+    let path = dir().join(format!("{}.rstn", name));
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, source).unwrap();
+    run(&dir(), args, path.to_str().unwrap())
+}
+
+// Compiles the file at `path` from the `cwd` directory
+pub fn run(cwd: &Path, args: &[&str], path: &str) -> Compiled {
+    let output = Command::new(env!("CARGO_BIN_EXE_ruston")).current_dir(cwd).args(args).arg(path).output().unwrap();
+    Compiled {
+        success: output.status.success(),
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+    }
+}
+
+// Like compile, but the program has to compile
+pub fn python(name: &str, source: &str, args: &[&str]) -> String {
+    let compiled = compile(name, source, args);
+    assert!(compiled.success, "{} didn't compile:\n{}", name, compiled.stderr);
+    compiled.stdout
+}
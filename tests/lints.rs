@@ -0,0 +1,199 @@
+mod common;
+
+use common::{compile, dir, Compiled};
+
+// What a lint reports for a compiled program, at the given level
+fn findings(compiled: &Compiled, level: &str, lint: &str) -> Vec<String> {
+    let prefix = format!("{} [{}]: ", level, lint);
+    compiled.stderr.lines().filter_map(|line| line.strip_prefix(&prefix)).map(str::to_string).collect()
+}
+
+// The warnings a lint gives for a program that compiles
+fn warnings_with(name: &str, source: &str, args: &[&str], lint: &str) -> Vec<String> {
+    let compiled = compile(name, source, args);
+    assert!(compiled.success, "{} didn't compile:\n{}", name, compiled.stderr);
+    findings(&compiled, "Warning", lint)
+}
+
+fn warnings(name: &str, source: &str, lint: &str) -> Vec<String> {
+    warnings_with(name, source, &[], lint)
+}
+
+#[test]
+fn unused_variable() {
+    let found = warnings("unused_variable", "let x = 1;\nlet y = 2;\nprint(y);\n", "unused_variables");
+    assert_eq!(found, ["Unused variable 'x' at line 1"]);
+}
+
+#[test]
+fn variable_only_assigned_to() {
+    let found = warnings("unused_assigned", "let x = 1;\nx = 2;\nx += 3;\n", "unused_variables");
+    assert_eq!(found, ["Variable 'x' declared at line 1 is assigned but never read"]);
+}
+
+#[test]
+fn unused_parameter() {
+    let source = "fn f(a: int, b: int) -> int {\n    return a;\n}\nprint(f(1, 2));\n";
+    let found = warnings("unused_parameter", source, "unused_variables");
+    assert_eq!(found, ["Unused parameter 'b' of function 'f' at line 1"]);
+}
+
+#[test]
+fn unused_loop_variable() {
+    let found = warnings("unused_loop_variable", "for (i in 0..3) {\n    print(1);\n}\n", "unused_variables");
+    assert_eq!(found, ["Unused variable 'i' at line 1"]);
+}
+
+#[test]
+fn underscore_opts_out_of_unused_variables() {
+    let source = "let _x = 1;\nfn f(_a: int) -> int {\n    return 1;\n}\nfor (_i in 0..3) {\n    print(f(2));\n}\n";
+    assert!(warnings("unused_underscore", source, "unused_variables").is_empty());
+}
+
+#[test]
+fn function_never_called() {
+    let source = "fn f() -> int {\n    return 1;\n}\nfn g() -> int {\n    return 2;\n}\nprint(g());\n";
+    let found = warnings("dead_function", source, "dead_code");
+    assert_eq!(found, ["Function 'f' declared at line 1 is never called"]);
+}
+
+#[test]
+fn recursion_doesnt_make_a_function_used() {
+    let source = "fn f(n: int) -> int {\n    return f(n - 1);\n}\n";
+    let found = warnings("dead_recursion", source, "dead_code");
+    assert_eq!(found, ["Function 'f' declared at line 1 is never called"]);
+}
+
+#[test]
+fn main_and_underscore_opt_out_of_dead_code() {
+    let source = "fn main() -> int {\n    return 0;\n}\nfn _helper() -> int {\n    return 1;\n}\n";
+    assert!(warnings("dead_opt_out", source, "dead_code").is_empty());
+}
+
+#[test]
+fn shadowing_is_allowed_by_default() {
+    let source = "let x = 1;\nfor (i in 0..3) {\n    let x = i;\n    print(x);\n}\nprint(x);\n";
+    assert!(warnings("shadowing_default", source, "shadowing").is_empty());
+    let found = warnings_with("shadowing_warn", source, &["-W", "shadowing"], "shadowing");
+    assert_eq!(found, ["Variable 'x' at line 3 shadows the one declared at line 1"]);
+}
+
+#[test]
+fn bool_comparison() {
+    let source = "let b = 1 < 2;\nlet c = b == true;\nlet d = false != b;\nlet e = b == c;\nprint(b, c, d, e);\n";
+    let found = warnings("bool_comparison", source, "bool_comparison");
+    assert_eq!(found, [
+        "Comparison with a bool literal at line 2, use the value or its negation",
+        "Comparison with a bool literal at line 3, use the value or its negation",
+    ]);
+}
+
+#[test]
+fn infinite_loop() {
+    let source = "fn f() -> int {\n    loop {\n        return 1;\n    }\n}\nprint(f());\nloop {\n    print(1);\n}\n";
+    let found = warnings("infinite_loop", source, "infinite_loop");
+    assert_eq!(found.len(), 1);
+    assert!(found[0].starts_with("The loop at line 7 never exits"), "{}", found[0]);
+}
+
+#[test]
+fn constant_condition() {
+    let source = "let b = 1 < 2;\nif (true) {\n    print(1);\n}\nif (1 > 2) {\n    print(2);\n}\nif (b) {\n    print(3);\n}\n";
+    let found = warnings("constant_condition", source, "constant_condition");
+    assert_eq!(found.len(), 2);
+    assert!(found[0].starts_with("The condition at line 2 is always true"), "{}", found[0]);
+    assert_eq!(found[1], "The condition at line 5 is constant");
+}
+
+#[test]
+fn empty_block() {
+    let source = "let b = 1 < 2;\nif (b) {\n} else {\n    print(1);\n}\nfor (_i in 0..3) {\n}\nif (b) {\n    print(2);\n}\n";
+    let found = warnings("empty_block", source, "empty_block");
+    assert_eq!(found, ["Empty if block at line 2", "Empty for block at line 6"]);
+}
+
+#[test]
+fn denied_lints_stop_the_compilation() {
+    let compiled = compile("deny_lint", "let x = 1;\n", &["-D", "unused_variables"]);
+    assert!(!compiled.success);
+    assert_eq!(findings(&compiled, "Error", "unused_variables"), ["Unused variable 'x' at line 1"]);
+    let compiled = compile("deny_warnings", "let x = 1;\n", &["-D", "warnings"]);
+    assert!(!compiled.success);
+}
+
+#[test]
+fn allowed_lints_are_silent() {
+    assert!(warnings_with("allow_lint", "let x = 1;\n", &["-A", "unused_variables"], "unused_variables").is_empty());
+}
+
+#[test]
+fn levels_come_from_the_closest_config() {
+    std::fs::create_dir_all(dir().join("config")).unwrap();
+    std::fs::write(dir().join("config/ruston.toml"), "[lints]\nunused_variables = \"allow\"\nshadowing = \"warn\"\n").unwrap();
+    let source = "let x = 1;\nif (x > 0) {\n    let x = 2;\n    print(x);\n}\nlet y = 1;\n";
+    assert!(warnings("config/levels", source, "unused_variables").is_empty());
+    assert_eq!(warnings("config/levels", source, "shadowing").len(), 1);
+    // the command line wins over the config
+    assert_eq!(warnings_with("config/levels", source, &["-W", "unused_variables"], "unused_variables").len(), 1);
+}
+
+#[test]
+fn allow_attribute_covers_its_statement() {
+    let source = "#[allow(unused_variables)]\nlet x = 1;\nlet y = 2;\n#[allow(empty_block, constant_condition)]\nif (true) {\n}\n";
+    assert_eq!(warnings("allow_attribute", source, "unused_variables"), ["Unused variable 'y' at line 3"]);
+    assert!(warnings("allow_attribute", source, "empty_block").is_empty());
+    assert!(warnings("allow_attribute", source, "constant_condition").is_empty());
+}
+
+#[test]
+fn loop_with_a_break_exits() {
+    let source = "let a = 1;\nloop {\n    if (a > 0) {\n        break;\n    }\n}\nloop {\n    print(1);\n}\n";
+    let found = warnings("infinite_loop_break", source, "infinite_loop");
+    assert_eq!(found.len(), 1);
+    assert!(found[0].starts_with("The loop at line 7 never exits"), "{}", found[0]);
+}
+
+#[test]
+fn unreachable_code() {
+    let source = "fn f() -> int {\n    return 1;\n    print(2);\n}\nprint(f());\nloop {\n    break;\n    print(3);\n}\n";
+    let found = warnings("unreachable_code", source, "unreachable_code");
+    assert_eq!(found, ["Unreachable code at line 3, after a return", "Unreachable code at line 8, after a break"]);
+}
+
+#[test]
+fn code_after_a_conditional_break_is_reachable() {
+    let source = "for (i in 0..3) {\n    if (i > 1) {\n        break;\n    }\n    print(i);\n}\n";
+    assert!(warnings("reachable_after_break", source, "unreachable_code").is_empty());
+}
+
+#[test]
+fn allow_attribute_ends_with_its_statement() {
+    let source = "let c = 1 < 2;\n#[allow(unused_variables)]\nif (c) {\n    let a = 1;\n}\nlet b = 2;\n#[allow(unused_variables)]\nlet d = 1;\nlet e = 2;\n";
+    let found = warnings("allow_attribute_end", source, "unused_variables");
+    assert_eq!(found, ["Unused variable 'b' at line 6", "Unused variable 'e' at line 9"]);
+}
+
+#[test]
+fn allow_attribute_lists() {
+    let source = "#[allow()]\nlet x = 1;\n#[allow(unused_variables,)]\nlet y = 2;\n";
+    assert_eq!(warnings("allow_attribute_lists", source, "unused_variables"), ["Unused variable 'x' at line 2"]);
+    for (name, attribute, error) in [
+        ("allow_attribute_not_a_name", "#[allow(1)]", "Expected a lint name or ')'"),
+        ("allow_attribute_no_comma", "#[allow(unused_variables dead_code)]", "Expected ',' or ')' after a lint name"),
+    ] {
+        let compiled = compile(name, &format!("{}\nlet x = 1;\n", attribute), &[]);
+        assert!(!compiled.success);
+        assert!(compiled.stderr.contains(error), "{}", compiled.stderr);
+    }
+}
+
+#[test]
+fn config_is_found_from_a_relative_path() {
+    let project = dir().join("relative");
+    std::fs::create_dir_all(project.join("src")).unwrap();
+    std::fs::write(project.join("ruston.toml"), "[lints]\nunused_variables = \"deny\"\n").unwrap();
+    std::fs::write(project.join("src/main.rstn"), "let x = 1;\n").unwrap();
+    let compiled = common::run(&project.join("src"), &[], "main.rstn");
+    assert!(!compiled.success);
+    assert_eq!(findings(&compiled, "Error", "unused_variables"), ["Unused variable 'x' at line 1"]);
+}
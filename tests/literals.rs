@@ -0,0 +1,40 @@
+mod common;
+
+use common::{compile, python};
+
+#[test]
+fn nested_tuple_indexes() {
+    let code = python("literals_tuple_index", "let t = ((1, 2), 3);\nprint(t.0.1, t.1);\n", &[]);
+    assert!(code.contains("print(t[0][1], t[1])"));
+}
+
+#[test]
+fn malformed_tuple_indexes_are_rejected() {
+    for (name, index) in [("literals_tuple_index_dot", "t.0."), ("literals_tuple_index_exponent", "t.0.1e0"), ("literals_tuple_index_underscore", "t.0.1_0")] {
+        let compiled = compile(name, &format!("let t = ((1, 2), 3);\nprint({});\n", index), &[]);
+        assert!(!compiled.success, "{} compiled", index);
+        assert!(compiled.stderr.contains("is not a tuple index"), "{}", compiled.stderr);
+    }
+}
+
+#[test]
+fn exponent_without_digits_is_malformed() {
+    for (name, literal) in [("literals_exponent", "1e"), ("literals_exponent_sign", "2.5e+"), ("literals_exponent_underscore", "1e_")] {
+        let compiled = compile(name, &format!("let a = {};\nprint(a);\n", literal), &[]);
+        assert!(!compiled.success, "{} compiled", literal);
+        assert!(compiled.stderr.contains(&format!("Malformed float literal {}, the exponent has no digits", literal)), "{}", compiled.stderr);
+    }
+    let code = python("literals_exponent_digits", "let a = 1e3 + 2.5E-1_0;\nprint(a);\n", &[]);
+    assert!(code.contains("(1e3 + 2.5E-10)"));
+}
+
+#[test]
+fn invalid_numbers_stop_the_compilation() {
+    for (name, literal) in [("literals_hex_no_digits", "0x"), ("literals_binary_digit", "0b12"), ("literals_letter", "12abc")] {
+        let compiled = compile(name, &format!("let a = {};\nprint(a);\n", literal), &[]);
+        assert!(!compiled.success, "{} compiled", literal);
+        assert!(compiled.stderr.contains("Invalid number format"), "{}", compiled.stderr);
+    }
+    let code = python("literals_prefixes", "print(0xff, 0o17, 0b1_01);\n", &[]);
+    assert!(code.contains("print(255, 15, 5)"), "{}", code);
+}
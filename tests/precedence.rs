@@ -0,0 +1,45 @@
+mod common;
+
+use common::{compile, python};
+
+// The python for `let a = <expression>;`
+fn parsed(name: &str, expression: &str) -> String {
+    let code = python(name, &format!("let a = {};\nprint(a);\n", expression), &[]);
+    let line = code.lines().find(|line| line.starts_with("a = ")).unwrap();
+    line["a = ".len()..].to_string()
+}
+
+#[test]
+fn power_is_right_associative() {
+    assert_eq!(parsed("precedence_power", "2 ** 3 ** 2"), "(2 ** (3 ** 2))");
+}
+
+#[test]
+fn power_binds_tighter_than_unary_minus() {
+    assert_eq!(parsed("precedence_minus_power", "-2 ** 2"), "-(2 ** 2)");
+}
+
+#[test]
+fn arithmetic_is_left_associative() {
+    assert_eq!(parsed("precedence_minus", "1 - 2 - 3"), "((1 - 2) - 3)");
+    assert_eq!(parsed("precedence_product", "8 * 4 * 2"), "((8 * 4) * 2)");
+}
+
+#[test]
+fn product_binds_tighter_than_sum() {
+    assert_eq!(parsed("precedence_sum", "1 + 2 * 3"), "(1 + (2 * 3))");
+}
+
+#[test]
+fn comparison_binds_tighter_than_logic() {
+    assert_eq!(parsed("precedence_logic", "1 < 2 && 3 < 4 || false"), "(((1 < 2) and (3 < 4)) or False)");
+}
+
+#[test]
+fn comparisons_are_not_chained() {
+    for (name, expression) in [("precedence_chain", "1 < 2 < 3"), ("precedence_chain_equal", "1 == 2 == true")] {
+        let compiled = compile(name, &format!("let a = {};\n", expression), &[]);
+        assert!(!compiled.success);
+        assert!(compiled.stderr.contains("can't be chained"), "{}", compiled.stderr);
+    }
+}